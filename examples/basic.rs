@@ -4,21 +4,9 @@ This example covers all the functionality provided by the library. It connects t
 
 use std::time::Duration;
 
-// By default using the newest Scepter API.
-#[cfg(not(feature = "dcam560"))]
-use vzense_rust::scepter as camera_api;
-
-// Uses an older API specifically for the DCAM560 model.
-#[cfg(feature = "dcam560")]
-use vzense_rust::dcam560 as camera_api;
-
-use camera_api::{
-    device::Device,
-    frame::{get_color_frame, get_depth_scaled_u8_frame, read_next_frame},
-};
-
 use vzense_rust::{
     ColorFormat, ColorResolution, DEFAULT_PIXEL_COUNT, DEFAULT_RESOLUTION, Resolution,
+    camera::{Backend, initialize},
     util::{
         Counter, KeyboardEvent, color_map::TURBO, new_fixed_vec, touch_detector::TouchDetector,
     },
@@ -27,8 +15,16 @@ use vzense_rust::{
 #[show_image::main]
 fn main() -> Result<(), Box<dyn std::error::Error>> {
     //
+    // By default using the newest Scepter API. Run with `-- dcam560` (or enable the `dcam560` feature) to use the older API specifically for the DCAM560 model. Both APIs are compiled in, so the choice is made at runtime.
+    let backend = match std::env::args().nth(1).as_deref() {
+        Some("dcam560") => Backend::Dcam560,
+        Some("scepter") => Backend::Scepter,
+        _ if cfg!(feature = "dcam560") => Backend::Dcam560,
+        _ => Backend::Scepter,
+    };
+
     // Looking for a device. If a device has been found, it will be opened and a stream started.
-    let mut device = match Device::initialize(Duration::from_secs(3), true) {
+    let mut device = match initialize(backend, Duration::from_secs(3), true) {
         Ok(d) => d,
         Err(msg) => {
             println!("{}", msg);
@@ -40,30 +36,21 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
     device.set_frame_rate(30)?;
     println!("frame rate: {} fps", device.get_frame_rate()?);
 
-    // Choose the depth measuring range for DCAM560 (Near, Mid, or Far).
-    #[cfg(feature = "dcam560")]
-    {
-        device.set_depth_measuring_range(vzense_rust::DepthMeasuringRange::Near);
-
-        let range = device.get_depth_measuring_range();
-        println!("depth measuring range: {} mm to {} mm", range.0, range.1);
-    }
-
     // Choose between RGB and BGR color format, default is BGR.
     device.set_color_format(ColorFormat::Rgb);
 
-    // Choose the min/max depth in mm for the color mapping of the depth output. These values also bound the depths used in the `TochDetector` to reduce measuring artifacts. In the specs the depth measuring range for the NYX650 is given as min: 300 mm, max: 4500 mm. The depth measuring range for the DCAM560 depends on the range chosen above.
+    // Choose the min/max depth in mm for the color mapping of the depth output. These values also bound the depths used in the `TochDetector` to reduce measuring artifacts. In the specs the depth measuring range for the NYX650 is given as min: 300 mm, max: 4500 mm. The depth measuring range for the DCAM560 depends on the `DepthMeasuringRange` (Near, Mid, or Far), which can be chosen with `set_depth_measuring_range()`.
     device.set_depth_range(160, 1100);
 
     // Initialize the touch detector.
-    let mut touch_detector = TouchDetector::new(&device, 5.0, 50.0, 30, 5, DEFAULT_PIXEL_COUNT);
+    let mut touch_detector = TouchDetector::new(&*device, 5.0, 50.0, 30, 5, DEFAULT_PIXEL_COUNT);
 
     // Mapping color to depth frame. If set to true, the color_resolution is fixed to 640x480.
     device.map_color_to_depth(false);
 
     // Setting the color resolution. If not set the default will be 640x480.
     // If color is mapped to depth, color resolution setting will be ignored.
-    let color_resolution = device.set_color_resolution(ColorResolution::Res800x600);
+    let color_resolution = device.set_color_resolution(ColorResolution::Res800x600)?;
 
     // Vectors to store image data.
 
@@ -93,28 +80,29 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
     ///////////////////////////////////////////////////////////////////////////
     // main loop reading frames and displaying them
     loop {
-        // `read_next_frame()` must be called at the beginning of each loop to retrieve new data.
-
-        // Scepter API has an additional `max_wait_time_ms` paramter.
-        #[cfg(not(feature = "dcam560"))]
-        read_next_frame(&mut device, 500);
+        if stop.key_was_pressed() {
+            break;
+        }
 
-        #[cfg(feature = "dcam560")]
-        read_next_frame(&mut device);
+        // `read_next_frame()` must be called at the beginning of each loop to retrieve new data. A failed capture (e.g. a timeout) is skipped.
+        if let Err(e) = device.read_next_frame(500) {
+            println!("{}", e);
+            continue;
+        }
 
-        // IR (only for Scepter API) __________________________________________
+        // IR _________________________________________________________________
 
-        // get_ir_frame(&mut device, &mut ir);
+        // device.get_ir_frame(&mut ir)?;
 
         // update_window(&ir_window, &DEFAULT_RESOLUTION, &ir, Format::Mono);
 
         // depth ______________________________________________________________
 
         // raw depth data in mm
-        // get_depth_mm_u16_frame(&mut device, &mut depth_mm);
+        // device.get_depth_mm_u16_frame(&mut depth_mm)?;
 
         // scaled depth data
-        get_depth_scaled_u8_frame(&mut device, &mut depth_scaled);
+        device.get_depth_scaled_u8_frame(&mut depth_scaled)?;
 
         // apply color map
         for (i, dsi) in depth_scaled.iter().enumerate() {
@@ -125,13 +113,13 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
 
         // touch detector
         // should be called after get_depth... call, otherwise `process` does nothing.
-        touch_detector.process(&device, &mut touch, &mut distance);
+        touch_detector.process(&*device, &mut touch, &mut distance);
 
         update_window(&touch_window, &DEFAULT_RESOLUTION, &touch, Format::Mono);
 
         // color ______________________________________________________________
 
-        get_color_frame(&mut device, &mut color_rgb);
+        device.get_color_frame(&mut color_rgb)?;
 
         if init {
             init = false;
@@ -147,10 +135,6 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
         if let Some(info) = counter.fps_frame_count_info() {
             set_window_title(&color_window, info);
         }
-
-        if stop.key_was_pressed() {
-            break;
-        }
     }
 
     stop.join();
@@ -0,0 +1,78 @@
+//! Calibration of the depth and color sensors as provided by the APIs. With the optional `serde` feature, `Intrinsics` and `Extrinsics` can be serialized, e.g. to archive the calibration alongside recordings.
+
+use crate::Resolution;
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+/// Intrinsic parameters of a sensor using the pinhole camera model with radial (`k1` to `k6`) and tangential (`p1`, `p2`) distortion as in OpenCV.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Intrinsics {
+    /// Focal length in x in pixels.
+    pub fx: f64,
+    /// Focal length in y in pixels.
+    pub fy: f64,
+    /// Principal point in x in pixels.
+    pub cx: f64,
+    /// Principal point in y in pixels.
+    pub cy: f64,
+    pub k1: f64,
+    pub k2: f64,
+    pub k3: f64,
+    pub k4: f64,
+    pub k5: f64,
+    pub k6: f64,
+    pub p1: f64,
+    pub p2: f64,
+}
+impl Intrinsics {
+    /// The intrinsics for the sensor read out at `to` instead of `from`, e.g. for another color resolution with the same aspect ratio. The distortion coefficients do not depend on the resolution.
+    pub fn scaled(&self, from: Resolution, to: Resolution) -> Self {
+        let (from_width, from_height) = from.to_tuple();
+        let (to_width, to_height) = to.to_tuple();
+        let sx = to_width as f64 / from_width as f64;
+        let sy = to_height as f64 / from_height as f64;
+        Self {
+            fx: self.fx * sx,
+            fy: self.fy * sy,
+            cx: self.cx * sx,
+            cy: self.cy * sy,
+            ..*self
+        }
+    }
+}
+
+/// Extrinsic parameters, the rotation and translation from the coordinate system of the depth sensor to that of the color sensor.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Extrinsics {
+    /// Rotation matrix, `rotation[row][column]`.
+    pub rotation: [[f64; 3]; 3],
+    /// Translation in mm.
+    pub translation: [f64; 3],
+}
+impl Extrinsics {
+    /// Creates the extrinsics from a rotation matrix stored row by row, as provided by the APIs.
+    pub(crate) fn from_arrays(rotation: [f64; 9], translation: [f64; 3]) -> Self {
+        Self {
+            rotation: [
+                [rotation[0], rotation[1], rotation[2]],
+                [rotation[3], rotation[4], rotation[5]],
+                [rotation[6], rotation[7], rotation[8]],
+            ],
+            translation,
+        }
+    }
+
+    /// Transforms a point from the coordinate system of the depth sensor to that of the color sensor.
+    pub fn transform(&self, point: [f64; 3]) -> [f64; 3] {
+        let [r0, r1, r2] = &self.rotation;
+        let dot = |r: &[f64; 3]| r[0] * point[0] + r[1] * point[1] + r[2] * point[2];
+        [
+            dot(r0) + self.translation[0],
+            dot(r1) + self.translation[1],
+            dot(r2) + self.translation[2],
+        ]
+    }
+}
@@ -0,0 +1,369 @@
+//! A backend-agnostic interface to devices of both APIs, allowing one binary to drive either camera family at runtime.
+
+use std::time::Duration;
+
+use crate::{
+    Alignment, ColorFormat, ColorResolution, DepthMeasuringRange, DeviceInfo, Error, Resolution,
+    calibration::{Extrinsics, Intrinsics},
+    dcam560,
+    filter::DepthFilterConfig,
+    frame::{ColorFrame, DepthFrame, FrameSet, FrameView, IrFrame},
+    point_cloud::PointCloud,
+    scepter,
+    util::touch_detector::Data,
+};
+
+/// The camera APIs supported by this library.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Backend {
+    /// The latest Scepter API for NYX650/660, DS86/87, DS77C, and DS77 cameras.
+    Scepter,
+    /// The older API specifically for the DCAM560 camera.
+    Dcam560,
+}
+
+/// Common interface implemented by `scepter::device::Device` and `dcam560::device::Device`. The frame getters correspond to the free functions in the `frame` modules of the respective API.
+pub trait Camera: Data + Send {
+    /// The API used by this camera.
+    fn backend(&self) -> Backend;
+
+    /// Get the current frame rate of the camera.
+    fn get_frame_rate(&self) -> Result<u8, Error>;
+
+    /// Set the ToF frame rate. Different devices have different maximum values. Please refer to the device specification.
+    fn set_frame_rate(&self, rate: u8) -> Result<(), Error>;
+
+    /// Get frame info like frame type, pixel format, width, height, etc.
+    fn get_frame_info(&self) -> String;
+
+    /// Checks if the number of pixels in the current frame equals `pixel_count`.
+    fn check_pixel_count(&self, pixel_count: usize);
+
+    /// Set the color frame format to either RGB or BGR.
+    fn set_color_format(&self, format: ColorFormat);
+
+    /// Enable or disable the mapping of the color image to depth camera space.
+    fn map_color_to_depth(&mut self, is_enabled: bool);
+
+    /// Sets how depth and color frames are aligned, see `Alignment`.
+    fn set_alignment(&mut self, alignment: Alignment) -> Result<(), Error>;
+
+    /// Returns how depth and color frames are aligned.
+    fn get_alignment(&self) -> Alignment;
+
+    /// Sets the resolution of the color frame and also returns it.
+    fn set_color_resolution(&mut self, resolution: ColorResolution) -> Result<Resolution, Error>;
+
+    /// Returns the resolution of the color frame.
+    fn get_color_resolution(&self) -> Result<Resolution, Error>;
+
+    /// Choosing the min/max depth in mm for the color mapping of the depth output.
+    fn set_depth_range(&mut self, min_depth_mm: u16, max_depth_mm: u16);
+
+    /// Sets the depth measuring range of the camera. Only provided by the DCAM560, returns `Error::Unsupported` for Scepter devices.
+    fn set_depth_measuring_range(&self, depth_range: DepthMeasuringRange) -> Result<(), Error>;
+
+    /// Resolution of the depth sensor.
+    fn get_depth_resolution(&self) -> Result<Resolution, Error>;
+
+    /// Intrinsic parameters of the depth sensor.
+    fn get_depth_intrinsics(&self) -> Result<Intrinsics, Error>;
+
+    /// Intrinsic parameters of the color sensor.
+    fn get_color_intrinsics(&self) -> Result<Intrinsics, Error>;
+
+    /// Extrinsic parameters from the depth sensor to the color sensor.
+    fn get_extrinsics(&self) -> Result<Extrinsics, Error>;
+
+    /// Applies all settings of the depth filter configuration which are not `None`, see `DepthFilterConfig`.
+    fn set_depth_filters(&self, config: &DepthFilterConfig) -> Result<(), Error>;
+
+    /// Reads back the configuration of the depth filters. Settings not provided by the device are `None`.
+    fn get_depth_filters(&self) -> Result<DepthFilterConfig, Error>;
+
+    /// Captures the next image frame. This function must be called before getting frame data using `get_..._frame()`. `max_wait_time_ms` is the maximum waiting time for the next frame in milliseconds. The recommended value is 2000 / fps.
+    fn read_next_frame(&mut self, max_wait_time_ms: u16) -> Result<(), Error>;
+
+    /// Raw depth data in mm as `u16`. Returns `false` if no depth frame was ready.
+    fn get_depth_mm_u16_frame(&mut self, depth_mm: &mut [u16]) -> Result<bool, Error>;
+
+    /// Depth data scaled according to min depth = 0 and max depth = 255 stored in a `u8` array. Returns `false` if no depth frame was ready.
+    fn get_depth_scaled_u8_frame(&mut self, depth_scaled: &mut [u8]) -> Result<bool, Error>;
+
+    /// IR data as `u8`. Returns `false` if no IR frame was ready.
+    fn get_ir_frame(&mut self, ir: &mut [u8]) -> Result<bool, Error>;
+
+    /// Color data as 24 bit stored in consecutive `u8`. Returns `false` if no color frame was ready.
+    fn get_color_frame(&mut self, color: &mut [u8]) -> Result<bool, Error>;
+
+    /// Depth frame in mm including resolution and metadata. Returns `None` if no depth frame was ready.
+    fn depth_frame(&mut self) -> Result<Option<DepthFrame>, Error>;
+
+    /// IR frame including resolution and metadata. Returns `None` if no IR frame was ready.
+    fn ir_frame(&mut self) -> Result<Option<IrFrame>, Error>;
+
+    /// Color frame including resolution and metadata. Returns `None` if no color frame was ready.
+    fn color_frame(&mut self) -> Result<Option<ColorFrame>, Error>;
+
+    /// Depth frame in mm mapped to color camera space at the color resolution. Requires `Alignment::DepthToColor`. Returns `None` if no such frame was ready.
+    fn aligned_depth_frame(&mut self) -> Result<Option<DepthFrame>, Error>;
+
+    /// Zero-copy view of the depth frame, valid until the next `read_next_frame()`. Returns `None` if no depth frame was ready.
+    fn depth_view(&self) -> Result<Option<FrameView<'_>>, Error>;
+
+    /// Zero-copy view of the IR frame, valid until the next `read_next_frame()`. Returns `None` if no IR frame was ready.
+    fn ir_view(&self) -> Result<Option<FrameView<'_>>, Error>;
+
+    /// Zero-copy view of the color frame, valid until the next `read_next_frame()`. Returns `None` if no color frame was ready.
+    fn color_view(&self) -> Result<Option<FrameView<'_>>, Error>;
+
+    /// Point cloud of the depth frame of the current capture computed by the API, e.g. to cross-check `point_cloud::deproject()`. Returns `None` if no depth frame was ready.
+    fn sdk_point_cloud(&self) -> Result<Option<PointCloud>, Error>;
+
+    /// All frames which were ready in the current capture.
+    fn frame_set(&mut self) -> Result<FrameSet, Error>;
+
+    /// Captures the next image frame and returns all frames which were ready in this capture.
+    fn read_frame_set(&mut self, max_wait_time_ms: u16) -> Result<FrameSet, Error> {
+        self.read_next_frame(max_wait_time_ms)?;
+        self.frame_set()
+    }
+
+    /// Stops the stream without closing the device.
+    fn stop_stream(&self) -> Result<(), Error>;
+
+    /// Stops the stream and closes the device. The API is shut down when no other device of the same API is alive.
+    fn shut_down(&mut self, verbose: bool);
+
+    /// Stops the stream and closes the device, returning the error if closing failed. Dropping the camera closes it as well.
+    fn close(self: Box<Self>) -> Result<(), Error>;
+}
+
+/// Initializes the system of the chosen `backend` and returns a device if it finds one. See `Device::initialize()` of the respective API for the meaning of `scan_time` and `verbose`.
+pub fn initialize(
+    backend: Backend,
+    scan_time: Duration,
+    verbose: bool,
+) -> Result<Box<dyn Camera>, Error> {
+    Ok(match backend {
+        Backend::Scepter => Box::new(scepter::device::Device::initialize(scan_time, verbose)?),
+        Backend::Dcam560 => Box::new(dcam560::device::Device::initialize(scan_time, verbose)?),
+    })
+}
+
+/// Returns info about all devices of the chosen `backend`. See `list_devices()` of the respective API.
+pub fn list_devices(backend: Backend, scan_time: Duration) -> Result<Vec<DeviceInfo>, Error> {
+    match backend {
+        Backend::Scepter => scepter::device::list_devices(scan_time),
+        Backend::Dcam560 => dcam560::device::list_devices(scan_time),
+    }
+}
+
+/// Opens the device described by `info` as returned by `list_devices()` using the API the device belongs to.
+pub fn open(info: &DeviceInfo, verbose: bool) -> Result<Box<dyn Camera>, Error> {
+    Ok(match info.backend {
+        Backend::Scepter => Box::new(scepter::device::Device::open(info, verbose)?),
+        Backend::Dcam560 => Box::new(dcam560::device::Device::open(info, verbose)?),
+    })
+}
+
+/// Opens the device of `backend` with serial number `serial`, searching for it for up to `scan_time`. See `Device::open_by_serial_within()` of the respective API.
+pub fn open_by_serial(
+    backend: Backend,
+    serial: &str,
+    scan_time: Duration,
+    verbose: bool,
+) -> Result<Box<dyn Camera>, Error> {
+    Ok(match backend {
+        Backend::Scepter => Box::new(scepter::device::Device::open_by_serial_within(
+            serial, scan_time, verbose,
+        )?),
+        Backend::Dcam560 => Box::new(dcam560::device::Device::open_by_serial_within(
+            serial, scan_time, verbose,
+        )?),
+    })
+}
+
+/// A camera without device for tests of code using `Camera`.
+#[cfg(test)]
+pub(crate) mod mock {
+    use std::{cell::Cell, collections::VecDeque, time::Instant};
+
+    use super::*;
+
+    /// Returns scripted results of `read_next_frame()` and keeps the settings applied to it.
+    pub(crate) struct MockCamera {
+        /// Results of the next calls of `read_next_frame()`, `Ok(())` once all were returned.
+        pub reads: VecDeque<Result<(), Error>>,
+        /// `read_next_frame()` panics instead of returning a result.
+        pub panic_on_read: bool,
+        /// Frame rates above this are rejected with `Error::InvalidParameter`.
+        pub max_frame_rate: u8,
+        pub frame_rate: Cell<u8>,
+        pub alignment: Alignment,
+    }
+    impl Default for MockCamera {
+        fn default() -> Self {
+            Self {
+                reads: VecDeque::new(),
+                panic_on_read: false,
+                max_frame_rate: 30,
+                frame_rate: Cell::new(15),
+                alignment: Alignment::None,
+            }
+        }
+    }
+    impl MockCamera {
+        /// A camera returning `reads` from `read_next_frame()`.
+        pub fn with_reads(reads: impl IntoIterator<Item = Result<(), Error>>) -> Self {
+            Self {
+                reads: reads.into_iter().collect(),
+                ..Default::default()
+            }
+        }
+    }
+
+    impl Data for MockCamera {
+        fn get_frame_p_frame_data(&self) -> *mut u8 {
+            std::ptr::null_mut()
+        }
+        fn get_frame_data_len(&self) -> usize {
+            0
+        }
+        fn get_min_depth_mm(&self) -> u16 {
+            0
+        }
+        fn get_max_depth_mm(&self) -> u16 {
+            0
+        }
+        fn current_frame_is_depth(&self) -> bool {
+            false
+        }
+    }
+
+    impl Camera for MockCamera {
+        fn backend(&self) -> Backend {
+            Backend::Scepter
+        }
+        fn get_frame_rate(&self) -> Result<u8, Error> {
+            Ok(self.frame_rate.get())
+        }
+        fn set_frame_rate(&self, rate: u8) -> Result<(), Error> {
+            if rate > self.max_frame_rate {
+                return Err(Error::InvalidParameter("frame rate too high"));
+            }
+            self.frame_rate.set(rate);
+            Ok(())
+        }
+        fn get_frame_info(&self) -> String {
+            String::new()
+        }
+        fn check_pixel_count(&self, _pixel_count: usize) {}
+        fn set_color_format(&self, _format: ColorFormat) {}
+        fn map_color_to_depth(&mut self, is_enabled: bool) {
+            self.alignment = if is_enabled {
+                Alignment::ColorToDepth
+            } else {
+                Alignment::None
+            };
+        }
+        fn set_alignment(&mut self, alignment: Alignment) -> Result<(), Error> {
+            self.alignment = alignment;
+            Ok(())
+        }
+        fn get_alignment(&self) -> Alignment {
+            self.alignment
+        }
+        fn set_color_resolution(
+            &mut self,
+            _resolution: ColorResolution,
+        ) -> Result<Resolution, Error> {
+            self.get_color_resolution()
+        }
+        fn get_color_resolution(&self) -> Result<Resolution, Error> {
+            Ok(crate::DEFAULT_RESOLUTION)
+        }
+        fn set_depth_range(&mut self, _min_depth_mm: u16, _max_depth_mm: u16) {}
+        fn set_depth_measuring_range(
+            &self,
+            _depth_range: DepthMeasuringRange,
+        ) -> Result<(), Error> {
+            Err(Error::Unsupported("setting the depth measuring range"))
+        }
+        fn get_depth_resolution(&self) -> Result<Resolution, Error> {
+            Ok(crate::DEFAULT_RESOLUTION)
+        }
+        fn get_depth_intrinsics(&self) -> Result<Intrinsics, Error> {
+            Ok(Intrinsics::default())
+        }
+        fn get_color_intrinsics(&self) -> Result<Intrinsics, Error> {
+            Ok(Intrinsics::default())
+        }
+        fn get_extrinsics(&self) -> Result<Extrinsics, Error> {
+            Ok(Extrinsics::default())
+        }
+        fn set_depth_filters(&self, _config: &DepthFilterConfig) -> Result<(), Error> {
+            Ok(())
+        }
+        fn get_depth_filters(&self) -> Result<DepthFilterConfig, Error> {
+            Ok(DepthFilterConfig::default())
+        }
+        fn read_next_frame(&mut self, _max_wait_time_ms: u16) -> Result<(), Error> {
+            if self.panic_on_read {
+                panic!("reading the next frame failed");
+            }
+            self.reads.pop_front().unwrap_or(Ok(()))
+        }
+        fn get_depth_mm_u16_frame(&mut self, _depth_mm: &mut [u16]) -> Result<bool, Error> {
+            Ok(false)
+        }
+        fn get_depth_scaled_u8_frame(&mut self, _depth_scaled: &mut [u8]) -> Result<bool, Error> {
+            Ok(false)
+        }
+        fn get_ir_frame(&mut self, _ir: &mut [u8]) -> Result<bool, Error> {
+            Ok(false)
+        }
+        fn get_color_frame(&mut self, _color: &mut [u8]) -> Result<bool, Error> {
+            Ok(false)
+        }
+        fn depth_frame(&mut self) -> Result<Option<DepthFrame>, Error> {
+            Ok(None)
+        }
+        fn ir_frame(&mut self) -> Result<Option<IrFrame>, Error> {
+            Ok(None)
+        }
+        fn color_frame(&mut self) -> Result<Option<ColorFrame>, Error> {
+            Ok(None)
+        }
+        fn aligned_depth_frame(&mut self) -> Result<Option<DepthFrame>, Error> {
+            Ok(None)
+        }
+        fn depth_view(&self) -> Result<Option<FrameView<'_>>, Error> {
+            Ok(None)
+        }
+        fn ir_view(&self) -> Result<Option<FrameView<'_>>, Error> {
+            Ok(None)
+        }
+        fn color_view(&self) -> Result<Option<FrameView<'_>>, Error> {
+            Ok(None)
+        }
+        fn sdk_point_cloud(&self) -> Result<Option<PointCloud>, Error> {
+            Ok(None)
+        }
+        fn frame_set(&mut self) -> Result<FrameSet, Error> {
+            Ok(FrameSet {
+                depth: None,
+                ir: None,
+                color: None,
+                host_time: Instant::now(),
+            })
+        }
+        fn stop_stream(&self) -> Result<(), Error> {
+            Ok(())
+        }
+        fn shut_down(&mut self, _verbose: bool) {}
+        fn close(self: Box<Self>) -> Result<(), Error> {
+            Ok(())
+        }
+    }
+}
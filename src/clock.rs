@@ -0,0 +1,241 @@
+//! Mapping of device timestamps into the clocks of the host, to fuse camera data with data of other sensors. Devices synchronized by NTP or PTP (see `scepter::time_sync`) provide timestamps in ms since the Unix epoch, which are converted directly. For unsynchronized devices, `DriftEstimator` estimates offset and drift of the device clock from the capture times on the host.
+
+use std::{
+    collections::VecDeque,
+    time::{Duration, Instant, SystemTime},
+};
+
+use crate::frame::FrameSet;
+
+/// Converts the timestamp of a device synchronized by NTP or PTP, given in ms since the Unix epoch, into a `SystemTime`.
+pub fn to_system_time(device_timestamp_ms: u64) -> SystemTime {
+    SystemTime::UNIX_EPOCH + Duration::from_millis(device_timestamp_ms)
+}
+
+/// Converts the timestamp of a device synchronized by NTP or PTP, given in ms since the Unix epoch, into an `Instant` by comparing the current times of both host clocks. Returns `None` if the instant cannot be represented.
+pub fn to_instant(device_timestamp_ms: u64) -> Option<Instant> {
+    system_time_to_instant(to_system_time(device_timestamp_ms))
+}
+
+/// Estimates offset and drift of an unsynchronized device clock relative to the host clock. Samples pair the device timestamp (in ms) of a frame with the time on the host when it was captured, e.g. `FrameSet::host_time`. A linear fit over the latest samples averages out the jitter of the transfer, and its slope gives the drift.
+///
+/// Since the slope of the fit also absorbs the unit of the device clock, `to_instant()` and `to_system_time()` are correct for any unit, e.g. for the DCAM560 `hardware_timestamp`, whose unit is not documented by the API. Only `drift_ppm()` relies on the timestamps being in ms.
+#[derive(Clone, Debug)]
+pub struct DriftEstimator {
+    capacity: usize,
+    /// The first sample, all others are stored relative to it.
+    reference: Option<(u64, Instant)>,
+    /// Device time in ms and host time in s relative to `reference`.
+    samples: VecDeque<(f64, f64)>,
+}
+impl DriftEstimator {
+    /// Creates an estimator which fits the latest `capacity` samples (at least 2).
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            capacity: capacity.max(2),
+            reference: None,
+            samples: VecDeque::new(),
+        }
+    }
+
+    /// Adds a pair of device timestamp in ms and the time on the host when the frame was captured.
+    pub fn add_sample(&mut self, device_timestamp_ms: u64, host_time: Instant) {
+        let (device_reference, host_reference) = *self
+            .reference
+            .get_or_insert((device_timestamp_ms, host_time));
+        let device = device_timestamp_ms as f64 - device_reference as f64;
+        let host = signed_seconds(host_time, host_reference);
+        if self.samples.len() == self.capacity {
+            self.samples.pop_front();
+        }
+        self.samples.push_back((device, host));
+    }
+
+    /// Adds the earliest device timestamp of `frame_set` together with its `host_time`. Empty frame sets are ignored.
+    pub fn add_frame_set(&mut self, frame_set: &FrameSet) {
+        if let Some(timestamp) = frame_set.timestamps().into_iter().flatten().min() {
+            self.add_sample(timestamp, frame_set.host_time);
+        }
+    }
+
+    /// The number of samples used by the fit.
+    pub fn sample_count(&self) -> usize {
+        self.samples.len()
+    }
+
+    /// Removes all samples, e.g. after the device was reconnected and its clock restarted.
+    pub fn reset(&mut self) {
+        self.reference = None;
+        self.samples.clear();
+    }
+
+    /// The drift of the device clock in parts per million, positive if the device clock is slower than the host clock. Returns `None` until the device timestamps of at least two samples differ.
+    pub fn drift_ppm(&self) -> Option<f64> {
+        let (_, slope) = self.fit()?;
+        // slope is host seconds per device ms
+        Some((slope * 1000.0 - 1.0) * 1e6)
+    }
+
+    /// Maps a device timestamp in ms to an `Instant` on the host. Returns `None` if there are not enough samples or the instant cannot be represented.
+    pub fn to_instant(&self, device_timestamp_ms: u64) -> Option<Instant> {
+        let (device_reference, host_reference) = self.reference?;
+        let (offset, slope) = self.fit()?;
+        let device = device_timestamp_ms as f64 - device_reference as f64;
+        let host = offset + slope * device;
+        if host >= 0.0 {
+            host_reference.checked_add(Duration::try_from_secs_f64(host).ok()?)
+        } else {
+            host_reference.checked_sub(Duration::try_from_secs_f64(-host).ok()?)
+        }
+    }
+
+    /// Maps a device timestamp in ms to a `SystemTime` on the host, see `to_instant()`.
+    pub fn to_system_time(&self, device_timestamp_ms: u64) -> Option<SystemTime> {
+        instant_to_system_time(self.to_instant(device_timestamp_ms)?)
+    }
+
+    // private functions_______________________________________________________
+
+    /// Least squares fit of host time = offset + slope * device time.
+    fn fit(&self) -> Option<(f64, f64)> {
+        let n = self.samples.len() as f64;
+        let (sum_device, sum_host) = self
+            .samples
+            .iter()
+            .fold((0.0, 0.0), |(d, h), (device, host)| (d + device, h + host));
+        let (mean_device, mean_host) = (sum_device / n, sum_host / n);
+        let (covariance, variance) =
+            self.samples
+                .iter()
+                .fold((0.0, 0.0), |(c, v), (device, host)| {
+                    let d = device - mean_device;
+                    (c + d * (host - mean_host), v + d * d)
+                });
+        if variance == 0.0 {
+            return None;
+        }
+        let slope = covariance / variance;
+        Some((mean_host - slope * mean_device, slope))
+    }
+}
+
+// private functions_______________________________________________________
+
+/// `time - reference` in seconds, negative if `time` is earlier.
+fn signed_seconds(time: Instant, reference: Instant) -> f64 {
+    match time.checked_duration_since(reference) {
+        Some(duration) => duration.as_secs_f64(),
+        None => -reference.duration_since(time).as_secs_f64(),
+    }
+}
+
+fn system_time_to_instant(time: SystemTime) -> Option<Instant> {
+    let (now, system_now) = (Instant::now(), SystemTime::now());
+    match time.duration_since(system_now) {
+        Ok(ahead) => now.checked_add(ahead),
+        Err(e) => now.checked_sub(e.duration()),
+    }
+}
+
+fn instant_to_system_time(instant: Instant) -> Option<SystemTime> {
+    let (now, system_now) = (Instant::now(), SystemTime::now());
+    match instant.checked_duration_since(now) {
+        Some(ahead) => system_now.checked_add(ahead),
+        None => system_now.checked_sub(now.duration_since(instant)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// `time - reference` in µs.
+    fn micros(time: Instant, reference: Instant) -> f64 {
+        signed_seconds(time, reference) * 1e6
+    }
+
+    /// Feeds `count` samples of a device clock starting at `device_start` ms with a frame interval of 33 ms, running `drift_ppm` slower than the host clock, plus `jitter` (alternating sign) in ms on the host.
+    fn feed(
+        estimator: &mut DriftEstimator,
+        host_start: Instant,
+        device_start: u64,
+        drift_ppm: f64,
+        jitter: f64,
+        count: u64,
+    ) {
+        for i in 0..count {
+            let device_elapsed = 33.0 * i as f64;
+            let sign = if i % 2 == 0 { 1.0 } else { -1.0 };
+            let host_elapsed = device_elapsed * (1.0 + drift_ppm * 1e-6) + sign * jitter;
+            let host_time = host_start + Duration::from_secs_f64(host_elapsed / 1000.0);
+            estimator.add_sample(device_start + 33 * i, host_time);
+        }
+    }
+
+    #[test]
+    fn known_drift_and_offset() {
+        let host_start = Instant::now();
+        let mut estimator = DriftEstimator::new(100);
+        feed(&mut estimator, host_start, 5_000_000, 50.0, 0.0, 100);
+        assert!((estimator.drift_ppm().unwrap() - 50.0).abs() < 0.01);
+
+        // a timestamp 10 s after the first sample maps to 10 s plus the drift on the host
+        let expected = host_start + Duration::from_secs_f64(10.0 * (1.0 + 50e-6));
+        let instant = estimator.to_instant(5_010_000).unwrap();
+        assert!(micros(instant, expected).abs() < 1.0);
+
+        // timestamps before the first sample map to instants before it
+        let instant = estimator.to_instant(4_999_000).unwrap();
+        assert!((micros(instant, host_start) + 1e6 * (1.0 + 50e-6)).abs() < 1.0);
+    }
+
+    #[test]
+    fn jitter_is_averaged_out() {
+        let host_start = Instant::now();
+        let mut estimator = DriftEstimator::new(1000);
+        feed(&mut estimator, host_start, 0, -20.0, 2.0, 1000);
+        assert!((estimator.drift_ppm().unwrap() + 20.0).abs() < 1.0);
+        let instant = estimator.to_instant(0).unwrap();
+        assert!(micros(instant, host_start).abs() < 100.0);
+    }
+
+    #[test]
+    fn capacity_keeps_latest_samples() {
+        let host_start = Instant::now();
+        let mut estimator = DriftEstimator::new(10);
+        feed(&mut estimator, host_start, 0, 0.0, 0.0, 100);
+        assert_eq!(estimator.sample_count(), 10);
+        assert!(estimator.drift_ppm().unwrap().abs() < 0.01);
+        assert_eq!(DriftEstimator::new(0).capacity, 2);
+    }
+
+    #[test]
+    fn not_enough_samples() {
+        let mut estimator = DriftEstimator::new(10);
+        assert_eq!(estimator.drift_ppm(), None);
+        assert_eq!(estimator.to_instant(0), None);
+        let now = Instant::now();
+        estimator.add_sample(100, now);
+        estimator.add_sample(100, now + Duration::from_millis(1));
+        assert_eq!(estimator.drift_ppm(), None);
+        estimator.add_sample(200, now + Duration::from_millis(100));
+        assert!(estimator.drift_ppm().is_some());
+        estimator.reset();
+        assert_eq!(estimator.sample_count(), 0);
+        assert_eq!(estimator.drift_ppm(), None);
+    }
+
+    #[test]
+    fn synchronized_timestamps() {
+        assert_eq!(
+            to_system_time(1_700_000_000_123),
+            SystemTime::UNIX_EPOCH + Duration::from_millis(1_700_000_000_123)
+        );
+        let now_ms = SystemTime::now()
+            .duration_since(SystemTime::UNIX_EPOCH)
+            .unwrap()
+            .as_millis() as u64;
+        let instant = to_instant(now_ms).unwrap();
+        assert!(micros(instant, Instant::now()).abs() < 100_000.0);
+    }
+}
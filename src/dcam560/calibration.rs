@@ -0,0 +1,66 @@
+//! Calibration of the depth and color sensors, see `calibration`.
+
+use sys::PsReturnStatus_PsRetOK as OK;
+use vzense_sys::dcam560 as sys;
+
+use crate::{
+    DEFAULT_RESOLUTION, Error, Resolution,
+    calibration::{Extrinsics, Intrinsics},
+};
+
+use super::{SESSION_INDEX, device::Device};
+
+impl Device {
+    /// Intrinsic parameters of the depth sensor.
+    pub fn get_depth_intrinsics(&self) -> Result<Intrinsics, Error> {
+        self.get_intrinsics(sys::PsSensorType_PsDepthSensor)
+    }
+
+    /// Intrinsic parameters of the color sensor.
+    pub fn get_color_intrinsics(&self) -> Result<Intrinsics, Error> {
+        self.get_intrinsics(sys::PsSensorType_PsRgbSensor)
+    }
+
+    /// Resolution of the depth sensor. It is fixed at `DEFAULT_RESOLUTION` and cannot be queried from the API.
+    pub fn get_depth_resolution(&self) -> Result<Resolution, Error> {
+        Ok(DEFAULT_RESOLUTION)
+    }
+
+    /// Extrinsic parameters from the depth sensor to the color sensor.
+    pub fn get_extrinsics(&self) -> Result<Extrinsics, Error> {
+        let mut params = sys::PsCameraExtrinsicParameters::default();
+        let status = unsafe {
+            sys::Ps2_GetCameraExtrinsicParameters(self.handle, SESSION_INDEX, &mut params)
+        };
+        if status != OK {
+            return Err(Error::dcam560("get camera extrinsic parameters", status));
+        }
+        Ok(Extrinsics::from_arrays(params.rotation, params.translation))
+    }
+
+    // private functions_______________________________________________________
+
+    fn get_intrinsics(&self, sensor: sys::PsSensorType) -> Result<Intrinsics, Error> {
+        let mut params = sys::PsCameraParameters::default();
+        let status = unsafe {
+            sys::Ps2_GetCameraParameters(self.handle, SESSION_INDEX, sensor, &mut params)
+        };
+        if status != OK {
+            return Err(Error::dcam560("get camera parameters", status));
+        }
+        Ok(Intrinsics {
+            fx: params.fx,
+            fy: params.fy,
+            cx: params.cx,
+            cy: params.cy,
+            k1: params.k1,
+            k2: params.k2,
+            k3: params.k3,
+            k4: params.k4,
+            k5: params.k5,
+            k6: params.k6,
+            p1: params.p1,
+            p2: params.p2,
+        })
+    }
+}
@@ -1,47 +1,138 @@
 //! Basic routines to initialize or shut down a device and to set/get parameters.
 
-use std::{ffi::CStr, os::raw::c_char, thread::sleep, time::Duration};
+use std::{
+    ffi::{CStr, CString},
+    os::raw::c_char,
+    sync::Arc,
+    thread::sleep,
+    time::{Duration, Instant},
+};
 
 use sys::PsReturnStatus_PsRetOK as OK;
 use vzense_sys::dcam560 as sys;
 
-use crate::{ColorFormat, ColorResolution, DepthMeasuringRange, Resolution, cyan, red, yellow};
-
-use super::SESSION_INDEX;
+use crate::{
+    Alignment, ColorFormat, ColorResolution, ConnectStatus, DepthMeasuringRange, DeviceInfo, Error,
+    Resolution,
+    calibration::{Extrinsics, Intrinsics},
+    camera::Backend,
+    cyan,
+    device_info::string_from_c_chars,
+    filter::DepthFilterConfig,
+    frame::{ColorFrame, DepthFrame, FrameSet, FrameView, IrFrame},
+    point_cloud::PointCloud,
+    red,
+    stream::{self, DropPolicy, FrameStream},
+    yellow,
+};
+
+use super::{SESSION_INDEX, frame, sdk::Sdk};
+
+/// The maximum time `Device::open_by_serial()` searches for a device which is unknown to the API.
+pub const OPEN_SCAN_TIME: Duration = Duration::from_secs(3);
 
 /// The main interface to the camera.
 pub struct Device {
+    pub(super) sdk: Arc<Sdk>,
     pub(super) handle: sys::PsDeviceHandle,
     pub(super) frame_ready: sys::PsFrameReady,
     pub(super) frame: sys::PsFrame,
     pub(super) color_resolution: ColorResolution,
-    pub(super) color_is_mapped: bool,
+    pub(super) alignment: Alignment,
     pub(super) current_frame_is_depth: bool,
     pub(super) min_depth_mm: u16,
     pub(super) max_depth_mm: u16,
+    pub(super) capture_time: Instant,
+    pub(super) wait_time_ms: Option<u16>,
+    /// The data mode to restore when leaving WDR mode, `None` if WDR is not enabled.
+    pub(super) data_mode_before_wdr: Option<sys::PsDataMode>,
 }
 impl Device {
     /// Initializes the sytem and returns a device if it finds one. Make sure a Vzense camera is connected. `scan_time` should be at least one second to find a device. Set `scan_time = Duration::MAX` to scan until a device was found (useful to wait for reconnection after the connection to a device was interrupted).
-    pub fn initialize(scan_time: Duration, verbose: bool) -> Result<Self, String> {
-        initialize(verbose)?;
+    pub fn initialize(scan_time: Duration, verbose: bool) -> Result<Self, Error> {
+        let sdk = Sdk::get(verbose)?;
+
+        let device_count = get_device_count(scan_time, false, verbose)?;
 
-        let device_count = get_device_count(scan_time, verbose)?;
+        let info = get_device_info_list(device_count)?
+            .into_iter()
+            .next()
+            .ok_or(Error::NoDeviceFound)?;
 
-        let mut device = Device::open_device_by_ip(get_ip(device_count)?)?;
+        let device = Device::open_device_by_ip(sdk, &info.ip)?;
 
         if verbose {
-            let info = device.get_device_info(device_count)?;
+            let firmware = device.get_firmware_version()?;
             println!(
                 "{}",
-                cyan!("model: {}, IP: {}, firmware: {}", info[0], info[1], info[2])
+                cyan!(
+                    "model: {}, IP: {}, firmware: {}",
+                    info.model,
+                    info.ip,
+                    firmware
+                )
             );
         }
 
-        device.start_stream(verbose)?;
+        device.start(verbose)
+    }
+
+    /// Opens the device described by `info` as returned by `list_devices()`, and starts its stream.
+    pub fn open(info: &DeviceInfo, verbose: bool) -> Result<Self, Error> {
+        if info.backend != Backend::Dcam560 {
+            return Err(Error::InvalidParameter("device info of another API"));
+        }
+        Device::open_by_serial(&info.serial, verbose)
+    }
+
+    /// Opens the device with serial number `serial` and starts its stream. The device is searched for up to `OPEN_SCAN_TIME` if the API does not know it, e.g. because the API was shut down after `list_devices()`.
+    pub fn open_by_serial(serial: &str, verbose: bool) -> Result<Self, Error> {
+        Device::open_by_serial_within(serial, OPEN_SCAN_TIME, verbose)
+    }
+
+    /// Like `open_by_serial()` but searches for the device for up to `scan_time`, e.g. to wait for a device which lost its connection. Returns as soon as the device was found.
+    pub fn open_by_serial_within(
+        serial: &str,
+        scan_time: Duration,
+        verbose: bool,
+    ) -> Result<Self, Error> {
+        let sdk = Sdk::get(verbose)?;
+        find_device(serial, scan_time)?;
+        let device = Device::open_device_by_serial(sdk, serial)?;
+        if verbose {
+            println!("{}", cyan!("opened device with serial number {}", serial));
+        }
+        device.start(verbose)
+    }
+
+    /// Opens the device with IP address `ip` and starts its stream.
+    pub fn open_by_ip(ip: &str, verbose: bool) -> Result<Self, Error> {
+        let device = Device::open_device_by_ip(Sdk::get(verbose)?, ip)?;
+        if verbose {
+            println!("{}", cyan!("opened device with IP {}", ip));
+        }
+        device.start(verbose)
+    }
 
-        device.set_color_resolution(ColorResolution::Res640x480);
+    /// Moves the device to a background thread and returns an asynchronous stream of its frame sets, see `stream::async_stream()`.
+    #[cfg(feature = "async")]
+    pub fn frame_stream(
+        self,
+        capacity: usize,
+        policy: DropPolicy,
+        max_wait_time_ms: u16,
+    ) -> stream::AsyncFrameStream<Self> {
+        stream::async_stream(Box::new(self), capacity, policy, max_wait_time_ms)
+    }
 
-        Ok(device)
+    /// Moves the device to a background thread which captures frames continuously, see `stream::spawn_stream()`.
+    pub fn spawn_stream(
+        self,
+        capacity: usize,
+        policy: DropPolicy,
+        max_wait_time_ms: u16,
+    ) -> FrameStream<Self> {
+        stream::spawn_stream(Box::new(self), capacity, policy, max_wait_time_ms)
     }
 
     /// Choosing the min/max depth in mm for the color mapping of the depth output. These values also bound the depths used in the `util::TochDetector` to reduce measuring artifacts.
@@ -51,20 +142,20 @@ impl Device {
     }
 
     /// Get the current frame rate of the camera.
-    pub fn get_frame_rate(&self) -> Result<u8, String> {
+    pub fn get_frame_rate(&self) -> Result<u8, Error> {
         let mut rate = 0;
         let status = unsafe { sys::Ps2_GetTofFrameRate(self.handle, SESSION_INDEX, &mut rate) };
         if status != OK {
-            return Err(red!("get frame rate failed with status {}", status));
+            return Err(Error::dcam560("get frame rate", status));
         }
         Ok(rate)
     }
 
     /// Set the ToF frame rate. Different devices have different maximum values. Please refer to the device specification.
-    pub fn set_frame_rate(&self, rate: u8) -> Result<(), String> {
+    pub fn set_frame_rate(&self, rate: u8) -> Result<(), Error> {
         let status = unsafe { sys::Ps2_SetTofFrameRate(self.handle, SESSION_INDEX, rate) };
         if status != OK {
-            return Err(red!("set frame rate failed with status {}", status));
+            return Err(Error::dcam560("set frame rate", status));
         }
         Ok(())
     }
@@ -101,25 +192,47 @@ impl Device {
         }
     }
 
-    /// Enable or disable the mapping of the color image to depth camera space.
+    /// Enable or disable the mapping of the color image to depth camera space, see `set_alignment()`.
     pub fn map_color_to_depth(&mut self, is_enabled: bool) {
-        if self.color_resolution != ColorResolution::Res640x480 {
-            self.set_color_resolution(ColorResolution::Res640x480);
+        let alignment = if is_enabled {
+            Alignment::ColorToDepth
+        } else {
+            Alignment::None
+        };
+        if let Err(e) = self.set_alignment(alignment) {
+            println!("{}", red!("{}", e));
         }
-        unsafe {
-            // should actually be `Ps2_SetMapperEnabledRGBToDepth` but the names seem to be mixed up
-            sys::Ps2_SetMapperEnabledDepthToRGB(
-                self.handle,
-                SESSION_INDEX,
-                if is_enabled { 1 } else { 0 },
-            );
+    }
+
+    /// Sets how depth and color frames are aligned. For `Alignment::ColorToDepth` the color resolution is set to 640x480. For `Alignment::DepthToColor` the depth frame mapped to color space is retrieved with `aligned_depth_frame()` at the current color resolution. On error, the previous alignment and color resolution are kept.
+    pub fn set_alignment(&mut self, alignment: Alignment) -> Result<(), Error> {
+        let previous_resolution = self.color_resolution;
+        if alignment == Alignment::ColorToDepth
+            && previous_resolution != ColorResolution::Res640x480
+        {
+            self.set_color_resolution(ColorResolution::Res640x480)?;
+        }
+        if let Err(e) = self.set_mappers(alignment) {
+            if self.color_resolution != previous_resolution {
+                let _ = self.set_color_resolution(previous_resolution);
+            }
+            return Err(e);
         }
-        self.color_is_mapped = is_enabled;
+        self.alignment = alignment;
+        Ok(())
     }
 
-    /// Sets the resolution of the color frame. Three resolutions are currently available: 640x480, 800x600, and 1600x1200.
-    pub fn set_color_resolution(&mut self, resolution: ColorResolution) -> Resolution {
-        if self.color_is_mapped {
+    /// Returns how depth and color frames are aligned.
+    pub fn get_alignment(&self) -> Alignment {
+        self.alignment
+    }
+
+    /// Sets the resolution of the color frame and also returns it. Three resolutions are currently available: 640x480, 800x600, and 1600x1200.
+    pub fn set_color_resolution(
+        &mut self,
+        resolution: ColorResolution,
+    ) -> Result<Resolution, Error> {
+        if self.alignment == Alignment::ColorToDepth {
             println!(
                 "{}",
                 yellow!(
@@ -132,8 +245,9 @@ impl Device {
                 ColorResolution::Res800x600 => sys::PsResolution_PsRGB_Resolution_800_600,
                 ColorResolution::Res1600x1200 => sys::PsResolution_PsRGB_Resolution_1600_1200,
             };
-            unsafe {
-                sys::Ps2_SetRGBResolution(self.handle, SESSION_INDEX, res);
+            let status = unsafe { sys::Ps2_SetRGBResolution(self.handle, SESSION_INDEX, res) };
+            if status != OK {
+                return Err(Error::dcam560("set RGB resolution", status));
             }
             self.color_resolution = resolution;
         }
@@ -141,50 +255,56 @@ impl Device {
     }
 
     /// Returns the resolution of the color frame.
-    pub fn get_color_resolution(&self) -> Resolution {
+    pub fn get_color_resolution(&self) -> Result<Resolution, Error> {
         let mut resolution_type = 0;
-        unsafe {
-            sys::Ps2_GetRGBResolution(self.handle, SESSION_INDEX, &mut resolution_type);
+        let status =
+            unsafe { sys::Ps2_GetRGBResolution(self.handle, SESSION_INDEX, &mut resolution_type) };
+        if status != OK {
+            return Err(Error::dcam560("get RGB resolution", status));
         }
         match resolution_type {
-            2 => Resolution::new(640, 480),
-            5 => Resolution::new(800, 600),
-            4 => Resolution::new(1600, 1200),
-            _ => panic!("{}", red!("unknown rgb resolution")),
+            2 => Ok(Resolution::new(640, 480)),
+            5 => Ok(Resolution::new(800, 600)),
+            4 => Ok(Resolution::new(1600, 1200)),
+            _ => Err(Error::Unsupported("the reported RGB resolution")),
         }
     }
 
     /// Sets the depth range mode.
-    pub fn set_depth_measuring_range(&self, depth_range: DepthMeasuringRange) {
+    pub fn set_depth_measuring_range(&self, depth_range: DepthMeasuringRange) -> Result<(), Error> {
         let depth_range = match depth_range {
             DepthMeasuringRange::Near => 0,
             DepthMeasuringRange::Mid => 1,
             DepthMeasuringRange::Far => 2,
         };
-        unsafe {
-            sys::Ps2_SetDepthRange(self.handle, SESSION_INDEX, depth_range);
+        let status = unsafe { sys::Ps2_SetDepthRange(self.handle, SESSION_INDEX, depth_range) };
+        if status != OK {
+            return Err(Error::dcam560("set depth range", status));
         }
+        Ok(())
     }
 
     /// Returns the current depth measuring range `(min, max)` of the camera in mm.
-    pub fn get_depth_measuring_range(&self) -> (u16, u16) {
+    pub fn get_depth_measuring_range(&self) -> Result<(u16, u16), Error> {
         let mut depth_range = sys::PsDepthRange::default();
-
-        unsafe {
-            sys::Ps2_GetDepthRange(self.handle, SESSION_INDEX, &mut depth_range);
+        let status =
+            unsafe { sys::Ps2_GetDepthRange(self.handle, SESSION_INDEX, &mut depth_range) };
+        if status != OK {
+            return Err(Error::dcam560("get depth range", status));
         }
 
         let mut mr = sys::PsMeasuringRange::default();
-
-        unsafe {
-            sys::Ps2_GetMeasuringRange(self.handle, SESSION_INDEX, depth_range, &mut mr);
+        let status =
+            unsafe { sys::Ps2_GetMeasuringRange(self.handle, SESSION_INDEX, depth_range, &mut mr) };
+        if status != OK {
+            return Err(Error::dcam560("get measuring range", status));
         }
 
         match depth_range {
-            0 => (mr.effectDepthMinNear, mr.effectDepthMaxNear),
-            1 => (mr.effectDepthMinMid, mr.effectDepthMaxMid),
-            2 => (mr.effectDepthMinFar, mr.effectDepthMaxFar),
-            _ => panic!("{}", red!("unknown measuring range")),
+            0 => Ok((mr.effectDepthMinNear, mr.effectDepthMaxNear)),
+            1 => Ok((mr.effectDepthMinMid, mr.effectDepthMaxMid)),
+            2 => Ok((mr.effectDepthMinFar, mr.effectDepthMaxFar)),
+            _ => Err(Error::Unsupported("the reported depth range")),
         }
     }
 
@@ -196,102 +316,171 @@ impl Device {
     }
 
     /// Current data mode.
-    pub fn get_data_mode(&self) -> Result<u32, String> {
+    pub fn get_data_mode(&self) -> Result<u32, Error> {
         let mut data_mode = sys::PsDataMode::default();
         let status = unsafe { sys::Ps2_GetDataMode(self.handle, SESSION_INDEX, &mut data_mode) };
         if status != OK {
-            return Err(red!("get data mode failed with status {}", status));
+            return Err(Error::dcam560("get data mode", status));
         }
         Ok(data_mode)
     }
 
-    /// Stops the stream, closes the device, and clears all resources.
-    pub fn shut_down(&mut self, verbose: bool) {
-        unsafe {
-            sys::Ps2_StopStream(self.handle, SESSION_INDEX);
-            sys::Ps2_CloseDevice(&mut self.handle);
+    /// Stops the stream without closing the device. No frames can be read afterwards.
+    pub fn stop_stream(&self) -> Result<(), Error> {
+        let status = unsafe { sys::Ps2_StopStream(self.handle, SESSION_INDEX) };
+        if status != OK {
+            return Err(Error::dcam560("stop stream", status));
+        }
+        Ok(())
+    }
 
-            let status = sys::Ps2_Shutdown();
-            if status != OK {
-                println!("{}", red!("shut down failed with status: {}", status));
-            } else if verbose {
-                println!("shut down device successfully");
-            }
+    /// Stops the stream and closes the device. The API itself is shut down when the last device and `Sdk` are dropped, so other devices keep streaming. Calling it more than once has no effect. Dropping the device does the same, use `close()` to handle errors.
+    pub fn shut_down(&mut self, verbose: bool) {
+        match self.close_handle() {
+            Ok(()) if verbose => println!("shut down device successfully"),
+            Ok(()) => (),
+            Err(e) => println!("{}", red!("{}", e)),
         }
     }
 
-    /// Returns device info as an array of Strings: \[model, IP, firmware, serial number\]
-    pub fn get_device_info(&self, device_count: u32) -> Result<[String; 4], String> {
+    /// Stops the stream and closes the device like `shut_down()` but returns the error if closing failed.
+    pub fn close(mut self) -> Result<(), Error> {
+        self.close_handle()
+    }
+
+    /// The `Sdk` this device belongs to.
+    pub fn sdk(&self) -> &Arc<Sdk> {
+        &self.sdk
+    }
+
+    /// Returns device info of the first device in the device list as an array of Strings: \[model, IP, firmware, serial number\]. Use `list_devices()` to get the info of all devices.
+    pub fn get_device_info(&self, device_count: u32) -> Result<[String; 4], Error> {
         if device_count == 0 {
-            return Err(red!("no device to get info for"));
+            return Err(Error::NoDeviceFound);
         }
 
-        let mut device_info = sys::PsDeviceInfo::default();
+        let info = get_device_info_list(device_count)?
+            .into_iter()
+            .next()
+            .ok_or(Error::NoDeviceFound)?;
 
-        unsafe { sys::Ps2_GetDeviceListInfo(&mut device_info, device_count) };
+        let firmware = self.get_firmware_version()?;
 
-        let ip = device_info.ip.as_ptr();
-        let uri = device_info.uri.as_ptr(); // model_name:serial_number
-        let serial = device_info.alias.as_ptr(); // serial number
+        Ok([info.model, info.ip, firmware, info.serial])
+    }
 
-        let firmware = self
-            .get_firmware_version()
-            .expect(&red!("Cannot get firmware version"));
+    // private functions_______________________________________________________
 
-        Ok([
-            unsafe { CStr::from_ptr(uri) }
-                .to_str()
-                .unwrap()
-                .split(":")
-                .collect::<Vec<&str>>()[0]
-                .to_string(),
-            unsafe { CStr::from_ptr(ip) }.to_string_lossy().into_owned(),
-            firmware,
-            unsafe { CStr::from_ptr(serial) }
-                .to_string_lossy()
-                .into_owned(),
-        ])
+    /// Enables the mappers of the API for `alignment`. If this fails, the mappers are left as they were.
+    fn set_mappers(&self, alignment: Alignment) -> Result<(), Error> {
+        let depth_to_color = alignment == Alignment::DepthToColor;
+        let was_depth_to_color = self.alignment == Alignment::DepthToColor;
+        if depth_to_color != was_depth_to_color {
+            self.set_depth_to_color_enabled(depth_to_color)?;
+        }
+        let color_to_depth = alignment == Alignment::ColorToDepth;
+        let status = unsafe {
+            sys::Ps2_SetMapperEnabledDepthToRGB(self.handle, SESSION_INDEX, color_to_depth as u8)
+        };
+        if status != OK {
+            if depth_to_color != was_depth_to_color {
+                let _ = self.set_depth_to_color_enabled(was_depth_to_color);
+            }
+            return Err(Error::dcam560("set mapper enabled depth to RGB", status));
+        }
+        Ok(())
     }
 
-    // private functions_______________________________________________________
+    fn set_depth_to_color_enabled(&self, is_enabled: bool) -> Result<(), Error> {
+        let status = unsafe {
+            sys::Ps2_SetMapperEnabledRGBToDepth(self.handle, SESSION_INDEX, is_enabled as u8)
+        };
+        if status != OK {
+            return Err(Error::dcam560("set mapper enabled RGB to depth", status));
+        }
+        Ok(())
+    }
 
-    fn get_firmware_version(&self) -> Result<String, String> {
+    fn get_firmware_version(&self) -> Result<String, Error> {
         let mut buffer = [0; 64];
         match get_firmware_version(self.handle, &mut buffer) {
             OK => Ok(CStr::from_bytes_until_nul(&buffer)
                 .unwrap()
                 .to_string_lossy()
                 .into_owned()),
-            error_code => Err(format!("{}", error_code)),
+            status => Err(Error::dcam560("get firmware version", status)),
         }
     }
 
-    fn open_device_by_ip(ip: *const c_char) -> Result<Self, String> {
+    fn open_device_by_ip(sdk: Arc<Sdk>, ip: &str) -> Result<Self, Error> {
+        let ip =
+            CString::new(ip).map_err(|_| Error::InvalidParameter("IP contains a null byte"))?;
         let mut handle = 0 as sys::PsDeviceHandle;
-        let status = unsafe { sys::Ps2_OpenDeviceByIP(ip, &mut handle) };
+        let status = unsafe { sys::Ps2_OpenDeviceByIP(ip.as_ptr(), &mut handle) };
+        if status != OK {
+            return Err(Error::dcam560("open device", status));
+        }
+        Device::from_handle(sdk, handle)
+    }
+
+    fn open_device_by_serial(sdk: Arc<Sdk>, serial: &str) -> Result<Self, Error> {
+        let serial = CString::new(serial)
+            .map_err(|_| Error::InvalidParameter("serial number contains a null byte"))?;
+        let mut handle = 0 as sys::PsDeviceHandle;
+        let status = unsafe { sys::Ps2_OpenDeviceByAlias(serial.as_ptr(), &mut handle) };
+        if status != OK {
+            return Err(Error::dcam560("open device", status));
+        }
+        Device::from_handle(sdk, handle)
+    }
+
+    /// Stops the stream and closes the handle if it is still open.
+    fn close_handle(&mut self) -> Result<(), Error> {
+        if self.handle.is_null() {
+            return Ok(());
+        }
+        // the stream might already be stopped, which is fine when closing
+        unsafe { sys::Ps2_StopStream(self.handle, SESSION_INDEX) };
+        let status = unsafe { sys::Ps2_CloseDevice(&mut self.handle) };
+        self.handle = std::ptr::null_mut();
         if status != OK {
-            return Err(red!("open device failed with status {}", status));
+            return Err(Error::dcam560("close device", status));
         }
+        Ok(())
+    }
+
+    fn from_handle(sdk: Arc<Sdk>, handle: sys::PsDeviceHandle) -> Result<Self, Error> {
         if !handle.is_null() {
             Ok(Device {
+                sdk,
                 handle,
                 frame_ready: sys::PsFrameReady::default(),
                 frame: sys::PsFrame::default(),
                 color_resolution: ColorResolution::Res640x480,
-                color_is_mapped: false,
+                alignment: Alignment::None,
                 current_frame_is_depth: false,
                 min_depth_mm: 500,  // default value
                 max_depth_mm: 1000, // default value
+                capture_time: Instant::now(),
+                wait_time_ms: None,
+                data_mode_before_wdr: None,
             })
         } else {
-            Err(red!("device ptr is null"))
+            Err(Error::NullPointer("device handle"))
         }
     }
 
-    fn start_stream(&self, verbose: bool) -> Result<(), String> {
+    /// Starts the stream and sets the default color resolution.
+    fn start(mut self, verbose: bool) -> Result<Self, Error> {
+        self.start_stream(verbose)?;
+        self.set_color_resolution(ColorResolution::Res640x480)?;
+        Ok(self)
+    }
+
+    fn start_stream(&self, verbose: bool) -> Result<(), Error> {
         let status = unsafe { sys::Ps2_StartStream(self.handle, SESSION_INDEX) };
         if status != OK {
-            return Err(red!("start stream failed with status {}", status));
+            return Err(Error::dcam560("start stream", status));
         }
         if verbose {
             println!("stream started")
@@ -305,6 +494,18 @@ impl Device {
     }
 }
 
+/// Stops the stream and closes the device, so it is not left opened if e.g. the application panics.
+impl Drop for Device {
+    fn drop(&mut self) {
+        if let Err(e) = self.close_handle() {
+            println!("{}", red!("{}", e));
+        }
+    }
+}
+
+// The handle and frame pointers of the API are only used by one thread at a time, which is guaranteed by `&mut self` and moving the device.
+unsafe impl Send for Device {}
+
 /// `Data` trait to allow use of `Device` in `util::TouchDetector`.
 impl crate::util::touch_detector::Data for Device {
     fn get_frame_p_frame_data(&self) -> *mut u8 {
@@ -324,31 +525,131 @@ impl crate::util::touch_detector::Data for Device {
     }
 }
 
+/// `Camera` trait to allow use of `Device` independently of the API.
+impl crate::camera::Camera for Device {
+    fn backend(&self) -> Backend {
+        Backend::Dcam560
+    }
+    fn get_frame_rate(&self) -> Result<u8, Error> {
+        self.get_frame_rate()
+    }
+    fn set_frame_rate(&self, rate: u8) -> Result<(), Error> {
+        self.set_frame_rate(rate)
+    }
+    fn get_frame_info(&self) -> String {
+        self.get_frame_info()
+    }
+    fn check_pixel_count(&self, pixel_count: usize) {
+        self.check_pixel_count(pixel_count)
+    }
+    fn set_color_format(&self, format: ColorFormat) {
+        self.set_color_format(format)
+    }
+    fn map_color_to_depth(&mut self, is_enabled: bool) {
+        self.map_color_to_depth(is_enabled)
+    }
+    fn set_alignment(&mut self, alignment: Alignment) -> Result<(), Error> {
+        self.set_alignment(alignment)
+    }
+    fn get_alignment(&self) -> Alignment {
+        self.get_alignment()
+    }
+    fn set_color_resolution(&mut self, resolution: ColorResolution) -> Result<Resolution, Error> {
+        self.set_color_resolution(resolution)
+    }
+    fn get_color_resolution(&self) -> Result<Resolution, Error> {
+        self.get_color_resolution()
+    }
+    fn set_depth_range(&mut self, min_depth_mm: u16, max_depth_mm: u16) {
+        self.set_depth_range(min_depth_mm, max_depth_mm)
+    }
+    fn set_depth_measuring_range(&self, depth_range: DepthMeasuringRange) -> Result<(), Error> {
+        self.set_depth_measuring_range(depth_range)
+    }
+    fn read_next_frame(&mut self, max_wait_time_ms: u16) -> Result<(), Error> {
+        // the DCAM560 API sets the wait time separately, so only call it if it changed
+        if self.wait_time_ms != Some(max_wait_time_ms) {
+            self.set_wait_time(max_wait_time_ms);
+            self.wait_time_ms = Some(max_wait_time_ms);
+        }
+        frame::read_next_frame(self)
+    }
+    fn get_depth_mm_u16_frame(&mut self, depth_mm: &mut [u16]) -> Result<bool, Error> {
+        frame::get_depth_mm_u16_frame(self, depth_mm)
+    }
+    fn get_depth_scaled_u8_frame(&mut self, depth_scaled: &mut [u8]) -> Result<bool, Error> {
+        frame::get_depth_scaled_u8_frame(self, depth_scaled)
+    }
+    fn get_ir_frame(&mut self, ir: &mut [u8]) -> Result<bool, Error> {
+        frame::get_ir_scaled_u8_frame(self, ir)
+    }
+    fn get_color_frame(&mut self, color: &mut [u8]) -> Result<bool, Error> {
+        frame::get_color_frame(self, color)
+    }
+    fn depth_frame(&mut self) -> Result<Option<DepthFrame>, Error> {
+        frame::depth_frame(self)
+    }
+    fn ir_frame(&mut self) -> Result<Option<IrFrame>, Error> {
+        frame::ir_frame(self)
+    }
+    fn color_frame(&mut self) -> Result<Option<ColorFrame>, Error> {
+        frame::color_frame(self)
+    }
+    fn aligned_depth_frame(&mut self) -> Result<Option<DepthFrame>, Error> {
+        frame::aligned_depth_frame(self)
+    }
+    fn depth_view(&self) -> Result<Option<FrameView<'_>>, Error> {
+        frame::depth_view(self)
+    }
+    fn ir_view(&self) -> Result<Option<FrameView<'_>>, Error> {
+        frame::ir_view(self)
+    }
+    fn color_view(&self) -> Result<Option<FrameView<'_>>, Error> {
+        frame::color_view(self)
+    }
+    fn sdk_point_cloud(&self) -> Result<Option<PointCloud>, Error> {
+        frame::sdk_point_cloud(self)
+    }
+    fn frame_set(&mut self) -> Result<FrameSet, Error> {
+        frame::frame_set(self)
+    }
+    fn get_depth_resolution(&self) -> Result<Resolution, Error> {
+        self.get_depth_resolution()
+    }
+    fn get_depth_intrinsics(&self) -> Result<Intrinsics, Error> {
+        self.get_depth_intrinsics()
+    }
+    fn get_color_intrinsics(&self) -> Result<Intrinsics, Error> {
+        self.get_color_intrinsics()
+    }
+    fn get_extrinsics(&self) -> Result<Extrinsics, Error> {
+        self.get_extrinsics()
+    }
+    fn set_depth_filters(&self, config: &DepthFilterConfig) -> Result<(), Error> {
+        self.set_depth_filters(config)
+    }
+    fn get_depth_filters(&self) -> Result<DepthFilterConfig, Error> {
+        self.get_depth_filters()
+    }
+    fn stop_stream(&self) -> Result<(), Error> {
+        self.stop_stream()
+    }
+    fn shut_down(&mut self, verbose: bool) {
+        self.shut_down(verbose)
+    }
+    fn close(self: Box<Self>) -> Result<(), Error> {
+        (*self).close()
+    }
+}
+
 fn get_firmware_version(handle: sys::PsDeviceHandle, buffer: &mut [u8]) -> sys::PsReturnStatus {
     let len = buffer.len().try_into().unwrap();
     let ptr: *mut c_char = buffer.as_mut_ptr().cast();
     unsafe { sys::Ps2_GetFirmwareVersionNumber(handle, SESSION_INDEX, ptr, len) }
 }
 
-fn initialize(verbose: bool) -> Result<(), String> {
-    if verbose {
-        println!("initializing...");
-    }
-    let status = unsafe { sys::Ps2_Initialize() };
-
-    // status -101 is reinitialization
-    if status == -101 {
-        if verbose {
-            println!("reinitializing...");
-        }
-    } else if status != OK {
-        return Err(red!("initialization failed with status {}", status));
-    }
-    Ok(())
-}
-
-/// Tries to find devices every 200 ms for duration `scan_time`.
-fn get_device_count(scan_time: Duration, verbose: bool) -> Result<u32, String> {
+/// Tries to find devices every 200 ms for duration `scan_time`. If `find_all` is `true`, the whole `scan_time` is used and the largest number of devices found is returned, otherwise the scan stops as soon as a device was found.
+fn get_device_count(scan_time: Duration, find_all: bool, verbose: bool) -> Result<u32, Error> {
     if scan_time < Duration::from_secs(1) {
         println!(
             "{}",
@@ -359,6 +660,7 @@ fn get_device_count(scan_time: Duration, verbose: bool) -> Result<u32, String> {
     let try_count = scan_time.div_duration_f64(sleep_interval).ceil() as u64;
 
     let mut device_count = 0;
+    let mut max_device_count = 0;
     let mut times_tried = 0;
     let mut status;
     if verbose {
@@ -368,31 +670,86 @@ fn get_device_count(scan_time: Duration, verbose: bool) -> Result<u32, String> {
         status = unsafe { sys::Ps2_GetDeviceCount(&mut device_count) };
 
         if status != OK {
-            return Err(red!("get device count failed with status {}", status));
-        } else {
-            if device_count > 0 {
-                if verbose {
-                    println!("{}", cyan!("device found"));
-                }
-                break;
-            }
-            times_tried += 1;
-            if times_tried >= try_count {
-                return Err(red!("no device found"));
-            }
-            sleep(sleep_interval);
+            return Err(Error::dcam560("get device count", status));
+        }
+        max_device_count = max_device_count.max(device_count);
+        if max_device_count > 0 && !find_all {
+            break;
         }
+        times_tried += 1;
+        if times_tried >= try_count {
+            break;
+        }
+        sleep(sleep_interval);
+    }
+    if max_device_count == 0 {
+        return Err(Error::NoDeviceFound);
+    }
+    if verbose {
+        println!("{}", cyan!("device found"));
+    }
+    Ok(max_device_count)
+}
+
+/// Returns info about all devices found. Call `Device::open()` with one of the infos to open the device. Holding an `Sdk` (see `Sdk::list_devices()`) until the device is opened keeps the API from forgetting the found devices, otherwise `open()` searches for the device again. The whole `scan_time` is used to find all devices, it should be at least one second.
+pub fn list_devices(scan_time: Duration) -> Result<Vec<DeviceInfo>, Error> {
+    let _sdk = Sdk::get(false)?;
+    match get_device_count(scan_time, true, false) {
+        Ok(device_count) => get_device_info_list(device_count),
+        Err(Error::NoDeviceFound) => Ok(Vec::new()),
+        Err(e) => Err(e),
     }
-    Ok(device_count)
 }
 
-fn get_ip(device_count: u32) -> Result<*const c_char, String> {
-    let mut device_info = sys::PsDeviceInfo::default();
-    unsafe {
-        let status = sys::Ps2_GetDeviceListInfo(&mut device_info, device_count);
+/// Searches for the device with serial number `serial` every 200 ms for at most `scan_time`. A device in limbo lost its connection and is only found again after it reconnected.
+fn find_device(serial: &str, scan_time: Duration) -> Result<(), Error> {
+    let scan_start = Instant::now();
+    loop {
+        let mut device_count = 0;
+        let status = unsafe { sys::Ps2_GetDeviceCount(&mut device_count) };
         if status != OK {
-            return Err(red!("get device list info failed with status {}", status));
+            return Err(Error::dcam560("get device count", status));
+        }
+        if device_count > 0
+            && get_device_info_list(device_count)?
+                .iter()
+                .any(|info| info.serial == serial && info.status != ConnectStatus::Limbo)
+        {
+            return Ok(());
+        }
+        if scan_start.elapsed() >= scan_time {
+            return Err(Error::NoDeviceFound);
         }
+        sleep(Duration::from_millis(200));
+    }
+}
+
+fn get_device_info_list(device_count: u32) -> Result<Vec<DeviceInfo>, Error> {
+    let mut device_infos = vec![sys::PsDeviceInfo::default(); device_count as usize];
+    let status = unsafe { sys::Ps2_GetDeviceListInfo(device_infos.as_mut_ptr(), device_count) };
+    if status != OK {
+        return Err(Error::dcam560("get device list info", status));
+    }
+    Ok(device_infos.iter().map(to_device_info).collect())
+}
+
+/// Converts the device info of the API.
+pub(super) fn to_device_info(info: &sys::PsDeviceInfo) -> DeviceInfo {
+    DeviceInfo {
+        backend: Backend::Dcam560,
+        // the uri has the form model_name:serial_number
+        model: string_from_c_chars(&info.uri)
+            .split(':')
+            .next()
+            .unwrap_or_default()
+            .to_string(),
+        serial: string_from_c_chars(&info.alias),
+        ip: string_from_c_chars(&info.ip),
+        status: match info.status {
+            sys::PsConnectStatus_Unconnected => ConnectStatus::Limbo,
+            sys::PsConnectStatus_Connected => ConnectStatus::Connectable,
+            sys::PsConnectStatus_Opened => ConnectStatus::Opened,
+            _ => ConnectStatus::Unknown,
+        },
     }
-    Ok(device_info.ip.as_ptr())
 }
@@ -0,0 +1,93 @@
+//! Depth filters running on the camera, configured with `DepthFilterConfig`. The DCAM560 only provides the time, confidence, and spatial filter, and the time filter has no threshold.
+
+use sys::PsReturnStatus_PsRetOK as OK;
+use vzense_sys::dcam560 as sys;
+
+use crate::{Error, camera::Backend, filter::DepthFilterConfig};
+
+use super::{SESSION_INDEX, device::Device};
+
+impl Device {
+    /// Applies all settings of `config` which are not `None`. Returns `Error::Unsupported` without changing anything if `config` contains a setting the DCAM560 does not provide.
+    pub fn set_depth_filters(&self, config: &DepthFilterConfig) -> Result<(), Error> {
+        config.validate(Backend::Dcam560)?;
+
+        if let Some(enable) = config.time_filter {
+            let status =
+                unsafe { sys::Ps2_SetTimeFilterEnabled(self.handle, SESSION_INDEX, enable as u8) };
+            if status != OK {
+                return Err(Error::dcam560("set time filter enabled", status));
+            }
+        }
+
+        if let Some(threshold) = config.confidence_threshold {
+            let status = unsafe {
+                sys::Ps2_SetConfidenceFilterThreshold(self.handle, SESSION_INDEX, threshold)
+            };
+            if status != OK {
+                return Err(Error::dcam560("set confidence filter threshold", status));
+            }
+        }
+
+        if let Some(enable) = config.confidence_filter {
+            let status = unsafe {
+                sys::Ps2_SetConfidenceFilterEnabled(self.handle, SESSION_INDEX, enable as u8)
+            };
+            if status != OK {
+                return Err(Error::dcam560("set confidence filter enabled", status));
+            }
+        }
+
+        if let Some(enable) = config.spatial_filter {
+            let status = unsafe {
+                sys::Ps2_SetSpatialFilterEnabled(self.handle, SESSION_INDEX, enable as u8)
+            };
+            if status != OK {
+                return Err(Error::dcam560("set spatial filter enabled", status));
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Reads back the current configuration of the depth filters. The settings not provided by the DCAM560 are `None`.
+    pub fn get_depth_filters(&self) -> Result<DepthFilterConfig, Error> {
+        let mut time = 0;
+        let status =
+            unsafe { sys::Ps2_GetTimeFilterEnabled(self.handle, SESSION_INDEX, &mut time) };
+        if status != OK {
+            return Err(Error::dcam560("get time filter enabled", status));
+        }
+
+        let mut confidence = 0;
+        let status = unsafe {
+            sys::Ps2_GetConfidenceFilterEnabled(self.handle, SESSION_INDEX, &mut confidence)
+        };
+        if status != OK {
+            return Err(Error::dcam560("get confidence filter enabled", status));
+        }
+
+        let mut threshold = 0;
+        let status = unsafe {
+            sys::Ps2_GetConfidenceFilterThreshold(self.handle, SESSION_INDEX, &mut threshold)
+        };
+        if status != OK {
+            return Err(Error::dcam560("get confidence filter threshold", status));
+        }
+
+        let mut spatial = 0;
+        let status =
+            unsafe { sys::Ps2_GetSpatialFilterEnabled(self.handle, SESSION_INDEX, &mut spatial) };
+        if status != OK {
+            return Err(Error::dcam560("get spatial filter enabled", status));
+        }
+
+        Ok(DepthFilterConfig {
+            time_filter: Some(time != 0),
+            confidence_filter: Some(confidence != 0),
+            confidence_threshold: Some(threshold),
+            spatial_filter: Some(spatial != 0),
+            ..Default::default()
+        })
+    }
+}
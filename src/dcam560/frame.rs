@@ -1,60 +1,56 @@
 //! Reading frames, retrieving data.
 
-use crate::red;
+use crate::frame::{
+    ColorFrame, DepthFrame, Frame, FrameMetadata, FrameSet, FrameView, IrFrame, PixelFormat,
+};
+use crate::point_cloud::PointCloud;
 use crate::util::{new_fixed_vec, normalize_u16_to_u8};
+use crate::{Alignment, Error};
 
 use super::SESSION_INDEX;
 
 use super::device::Device;
-use std::iter::zip;
+use std::{iter::zip, time::Instant};
 use sys::PsReturnStatus_PsRetOK as OK;
 use vzense_sys::dcam560 as sys;
 
-/// Captures the next image frame from `device`. This function must be called before getting frame data using `get_..._frame()`.
-pub fn read_next_frame(device: &mut Device) -> i32 {
-    unsafe {
-        let status = sys::Ps2_ReadNextFrame(device.handle, SESSION_INDEX, &mut device.frame_ready);
-        if status != OK {
-            println!(
-                "{}",
-                red!("vzense_rust: read_next_frame failed with status {}", status)
-            );
-            return status;
-        }
+/// Captures the next image frame from `device`. This function must be called before getting frame data using `get_..._frame()`. Returns `Error::Timeout` if no frame was ready in time and `Error::Disconnected` if the connection to the device was lost.
+pub fn read_next_frame(device: &mut Device) -> Result<(), Error> {
+    let status =
+        unsafe { sys::Ps2_ReadNextFrame(device.handle, SESSION_INDEX, &mut device.frame_ready) };
+    if status != OK {
+        device.frame_ready = sys::PsFrameReady::default();
+        return Err(Error::dcam560("read next frame", status));
     }
-    0
+    device.capture_time = Instant::now();
+    Ok(())
 }
 
-/// Raw depth data in mm as `u16`.
-pub fn get_depth_mm_u16_frame(device: &mut Device, depth_mm: &mut [u16]) {
-    if device.frame_ready.depth() == 1 {
+/// Raw depth data in mm as `u16`. Returns `false` if no depth frame was ready in the current capture.
+pub fn get_depth_mm_u16_frame(device: &mut Device, depth_mm: &mut [u16]) -> Result<bool, Error> {
+    if let Some(frame_type) = depth_frame_type(device) {
         let status = unsafe {
-            sys::Ps2_GetFrame(
-                device.handle,
-                SESSION_INDEX,
-                sys::PsFrameType_PsDepthFrame,
-                &mut device.frame,
-            )
+            sys::Ps2_GetFrame(device.handle, SESSION_INDEX, frame_type, &mut device.frame)
         };
-        check_frame(device, status);
+        check_frame(device, status)?;
         get_depth_mm(device, depth_mm);
 
         device.current_frame_is_depth = true;
+        return Ok(true);
     }
+    Ok(false)
 }
 
-/// Depth data scaled according to `device.min_depth_mm` = 0 and `device.max_depth_mm` = 255 stored in a `u8` array.
-pub fn get_depth_scaled_u8_frame(device: &mut Device, depth_scaled: &mut [u8]) {
-    if device.frame_ready.depth() == 1 {
+/// Depth data scaled according to `device.min_depth_mm` = 0 and `device.max_depth_mm` = 255 stored in a `u8` array. Returns `false` if no depth frame was ready in the current capture.
+pub fn get_depth_scaled_u8_frame(
+    device: &mut Device,
+    depth_scaled: &mut [u8],
+) -> Result<bool, Error> {
+    if let Some(frame_type) = depth_frame_type(device) {
         let status = unsafe {
-            sys::Ps2_GetFrame(
-                device.handle,
-                SESSION_INDEX,
-                sys::PsFrameType_PsDepthFrame,
-                &mut device.frame,
-            )
+            sys::Ps2_GetFrame(device.handle, SESSION_INDEX, frame_type, &mut device.frame)
         };
-        check_frame(device, status);
+        check_frame(device, status)?;
         let mut depth_mm = new_fixed_vec(depth_scaled.len(), 0);
 
         get_depth_mm(device, &mut depth_mm);
@@ -66,13 +62,15 @@ pub fn get_depth_scaled_u8_frame(device: &mut Device, depth_scaled: &mut [u8]) {
             depth_scaled,
         );
         device.current_frame_is_depth = true;
+        return Ok(true);
     }
+    Ok(false)
 }
 
 /// Frame contains no IR data, even if
 /// sys::Ps2_SetDataMode(.., .., sys::PsDataMode_PsIRAndRGB_30)
-/// is set.
-pub fn get_ir_scaled_u8_frame(device: &mut Device, ir_scaled: &mut [u8]) {
+/// is set. Returns `false` if no IR frame was ready in the current capture.
+pub fn get_ir_scaled_u8_frame(device: &mut Device, ir_scaled: &mut [u8]) -> Result<bool, Error> {
     if device.frame_ready.ir() == 1 {
         let status = unsafe {
             sys::Ps2_GetFrame(
@@ -82,26 +80,242 @@ pub fn get_ir_scaled_u8_frame(device: &mut Device, ir_scaled: &mut [u8]) {
                 &mut device.frame,
             )
         };
-        check_frame(device, status);
+        check_frame(device, status)?;
         get_normalized_ir(device, 0, 255, ir_scaled);
         device.current_frame_is_depth = false;
+        return Ok(true);
     }
+    Ok(false)
 }
 
-/// Color data as 24 bit stored in consecutive `u8`.
-pub fn get_color_frame(device: &mut Device, color: &mut [u8]) {
-    let frame_type = if device.color_is_mapped && device.frame_ready.mappedRGB() == 1 {
-        sys::PsFrameType_PsMappedRGBFrame
-    } else if !device.color_is_mapped && device.frame_ready.rgb() == 1 {
-        sys::PsFrameType_PsRGBFrame
-    } else {
-        return;
+/// Color data as 24 bit stored in consecutive `u8`. Returns `false` if no color frame was ready in the current capture.
+pub fn get_color_frame(device: &mut Device, color: &mut [u8]) -> Result<bool, Error> {
+    let Some(frame_type) = color_frame_type(device) else {
+        return Ok(false);
     };
     let status =
         unsafe { sys::Ps2_GetFrame(device.handle, SESSION_INDEX, frame_type, &mut device.frame) };
-    check_frame(device, status);
+    check_frame(device, status)?;
     get_color(device, color);
     device.current_frame_is_depth = false;
+    Ok(true)
+}
+
+/// Depth frame in mm including resolution and metadata. Returns `None` if no depth frame was ready in the current capture.
+pub fn depth_frame(device: &mut Device) -> Result<Option<DepthFrame>, Error> {
+    let Some(frame_type) = depth_frame_type(device) else {
+        return Ok(None);
+    };
+    get_frame(device, frame_type)?;
+    let mut data = new_fixed_vec(device.frame.dataLen as usize / 2, 0);
+    get_depth_mm(device, &mut data);
+    device.current_frame_is_depth = true;
+    Ok(Some(to_owned_frame(device, data)))
+}
+
+/// Depth frame in mm mapped to color camera space at the color resolution, including resolution and metadata. Requires `Alignment::DepthToColor`. Returns `None` if no such frame was ready in the current capture.
+pub fn aligned_depth_frame(device: &mut Device) -> Result<Option<DepthFrame>, Error> {
+    if device.alignment != Alignment::DepthToColor || device.frame_ready.mappedDepth() != 1 {
+        return Ok(None);
+    }
+    get_frame(device, sys::PsFrameType_PsMappedDepthFrame)?;
+    let mut data = new_fixed_vec(device.frame.dataLen as usize / 2, 0);
+    get_depth_mm(device, &mut data);
+    device.current_frame_is_depth = false;
+    Ok(Some(to_owned_frame(device, data)))
+}
+
+/// IR frame with 16 bit intensities including resolution and metadata. Returns `None` if no IR frame was ready in the current capture.
+pub fn ir_frame(device: &mut Device) -> Result<Option<IrFrame>, Error> {
+    if device.frame_ready.ir() != 1 {
+        return Ok(None);
+    }
+    get_frame(device, sys::PsFrameType_PsIRFrame)?;
+    let data = frame_data(device)
+        .chunks_exact(2)
+        .map(|pi| u16::from_le_bytes([pi[0], pi[1]]))
+        .collect();
+    device.current_frame_is_depth = false;
+    Ok(Some(to_owned_frame(device, data)))
+}
+
+/// Color frame including resolution and metadata. If color is mapped to depth, the mapped frame is returned. Returns `None` if no color frame was ready in the current capture.
+pub fn color_frame(device: &mut Device) -> Result<Option<ColorFrame>, Error> {
+    let Some(frame_type) = color_frame_type(device) else {
+        return Ok(None);
+    };
+    get_frame(device, frame_type)?;
+    let data = frame_data(device).to_vec();
+    device.current_frame_is_depth = false;
+    Ok(Some(to_owned_frame(device, data)))
+}
+
+/// All frames which were ready in the current capture. Unlike calling the single getters, the returned set makes explicit which frames belong to the same capture.
+pub fn frame_set(device: &mut Device) -> Result<FrameSet, Error> {
+    Ok(FrameSet {
+        depth: depth_frame(device)?,
+        ir: ir_frame(device)?,
+        color: color_frame(device)?,
+        host_time: device.capture_time,
+    })
+}
+
+/// Captures the next image frame and returns all frames which were ready in this capture, see `read_next_frame()` and `frame_set()`.
+pub fn read_frame_set(device: &mut Device) -> Result<FrameSet, Error> {
+    read_next_frame(device)?;
+    frame_set(device)
+}
+
+/// Zero-copy view of the depth frame, valid until the next `read_next_frame()`. Unlike `depth_frame()`, the view does not update the frame used by `util::TouchDetector`. Returns `None` if no depth frame was ready in the current capture.
+pub fn depth_view(device: &Device) -> Result<Option<FrameView<'_>>, Error> {
+    let Some(frame_type) = depth_frame_type(device) else {
+        return Ok(None);
+    };
+    get_view(device, frame_type).map(Some)
+}
+
+/// Zero-copy view of the IR frame, valid until the next `read_next_frame()`. Returns `None` if no IR frame was ready in the current capture.
+pub fn ir_view(device: &Device) -> Result<Option<FrameView<'_>>, Error> {
+    if device.frame_ready.ir() != 1 {
+        return Ok(None);
+    }
+    get_view(device, sys::PsFrameType_PsIRFrame).map(Some)
+}
+
+/// Zero-copy view of the color frame, valid until the next `read_next_frame()`. If color is mapped to depth, the mapped frame is returned. Returns `None` if no color frame was ready in the current capture.
+pub fn color_view(device: &Device) -> Result<Option<FrameView<'_>>, Error> {
+    let Some(frame_type) = color_frame_type(device) else {
+        return Ok(None);
+    };
+    get_view(device, frame_type).map(Some)
+}
+
+/// Point cloud of the depth frame of the current capture computed by the API, e.g. to cross-check `point_cloud::deproject()`. Returns `None` if no depth frame was ready in the current capture.
+pub fn sdk_point_cloud(device: &Device) -> Result<Option<PointCloud>, Error> {
+    let Some(frame_type) = depth_frame_type(device) else {
+        return Ok(None);
+    };
+    let mut frame = sys::PsFrame::default();
+    let status = unsafe { sys::Ps2_GetFrame(device.handle, SESSION_INDEX, frame_type, &mut frame) };
+    if status != OK {
+        return Err(Error::dcam560("get frame", status));
+    }
+    let (width, height) = (frame.width as u32, frame.height as u32);
+    let mut vectors = vec![sys::PsVector3f::default(); (width * height) as usize];
+    let status = unsafe {
+        sys::Ps2_ConvertDepthFrameToWorldVector(
+            device.handle,
+            SESSION_INDEX,
+            frame,
+            vectors.as_mut_ptr(),
+        )
+    };
+    if status != OK {
+        return Err(Error::dcam560(
+            "convert depth frame to world vector",
+            status,
+        ));
+    }
+    Ok(Some(PointCloud {
+        width,
+        height,
+        points: vectors.iter().map(|v| [v.x, v.y, v.z]).collect(),
+        colors: None,
+        intensities: None,
+    }))
+}
+
+/// The depth frame type to retrieve, the fused depth frame if WDR is enabled with `WdrStyle::Fusion`. `None` if no depth frame is ready.
+fn depth_frame_type(device: &Device) -> Option<sys::PsFrameType> {
+    if device.frame_ready.wdrDepth() == 1 {
+        Some(sys::PsFrameType_PsWDRDepthFrame)
+    } else if device.frame_ready.depth() == 1 {
+        Some(sys::PsFrameType_PsDepthFrame)
+    } else {
+        None
+    }
+}
+
+/// The color frame type to retrieve, depending on whether color is mapped to depth. `None` if no such frame is ready.
+fn color_frame_type(device: &Device) -> Option<sys::PsFrameType> {
+    if device.alignment == Alignment::ColorToDepth && device.frame_ready.mappedRGB() == 1 {
+        Some(sys::PsFrameType_PsMappedRGBFrame)
+    } else if device.alignment != Alignment::ColorToDepth && device.frame_ready.rgb() == 1 {
+        Some(sys::PsFrameType_PsRGBFrame)
+    } else {
+        None
+    }
+}
+
+/// Retrieves the frame of type `frame_type` into `device.frame`.
+fn get_frame(device: &mut Device, frame_type: sys::PsFrameType) -> Result<(), Error> {
+    let status =
+        unsafe { sys::Ps2_GetFrame(device.handle, SESSION_INDEX, frame_type, &mut device.frame) };
+    check_frame(device, status)
+}
+
+/// Retrieves the frame of type `frame_type` without touching `device.frame` and borrows its buffer.
+fn get_view(device: &Device, frame_type: sys::PsFrameType) -> Result<FrameView<'_>, Error> {
+    let mut frame = sys::PsFrame::default();
+    let status = unsafe { sys::Ps2_GetFrame(device.handle, SESSION_INDEX, frame_type, &mut frame) };
+    if status != OK {
+        return Err(Error::dcam560("get frame", status));
+    }
+    if frame.pFrameData.is_null() {
+        return Err(Error::NullPointer("frame data"));
+    }
+    // the buffer stays valid until the next call to read the next frame, which needs `&mut Device`
+    let data = unsafe { std::slice::from_raw_parts(frame.pFrameData, frame.dataLen as usize) };
+    Ok(FrameView {
+        width: frame.width as u32,
+        height: frame.height as u32,
+        pixel_format: pixel_format(frame.pixelFormat),
+        frame_index: frame.frameIndex,
+        metadata: metadata(&frame),
+        data,
+    })
+}
+
+/// Wraps `data` together with the resolution and metadata of the current frame.
+fn to_owned_frame<T>(device: &Device, data: Vec<T>) -> Frame<T> {
+    let frame = device.frame;
+    Frame {
+        width: frame.width as u32,
+        height: frame.height as u32,
+        pixel_format: pixel_format(frame.pixelFormat),
+        frame_index: frame.frameIndex,
+        metadata: metadata(&frame),
+        data,
+    }
+}
+
+fn metadata(frame: &sys::PsFrame) -> FrameMetadata {
+    FrameMetadata::Dcam560 {
+        exposure_time: frame.exposureTime,
+        depth_range: frame.depthRange,
+        imu_frame_no: frame.imuFrameNo,
+        hardware_timestamp: frame.hardwaretimestamp,
+    }
+}
+
+fn pixel_format(format: sys::PsPixelFormat) -> PixelFormat {
+    match format {
+        sys::PsPixelFormat_PsPixelFormatDepthMM16 => PixelFormat::DepthMm16,
+        sys::PsPixelFormat_PsPixelFormatGray16 => PixelFormat::Gray16,
+        sys::PsPixelFormat_PsPixelFormatGray8 => PixelFormat::Gray8,
+        sys::PsPixelFormat_PsPixelFormatRGB888 => PixelFormat::Rgb888,
+        sys::PsPixelFormat_PsPixelFormatBGR888 => PixelFormat::Bgr888,
+        other => PixelFormat::Unknown(other),
+    }
+}
+
+/// The data of the current frame as bytes.
+fn frame_data(device: &Device) -> &[u8] {
+    unsafe {
+        std::ptr::slice_from_raw_parts(device.frame.pFrameData, device.frame.dataLen as usize)
+            .as_ref()
+            .unwrap()
+    }
 }
 
 fn get_depth_mm(device: &Device, depth_mm: &mut [u16]) {
@@ -145,11 +359,13 @@ fn get_color(device: &Device, color: &mut [u8]) {
     }
 }
 
-fn check_frame(device: &Device, status: sys::PsReturnStatus) {
+/// Check status of `Ps2_GetFrame()` and if data pointer is null.
+fn check_frame(device: &Device, status: sys::PsReturnStatus) -> Result<(), Error> {
     if status != OK {
-        panic!("{}", red!("get_frame failed with status {}", status));
+        return Err(Error::dcam560("get frame", status));
     }
     if device.frame.pFrameData.is_null() {
-        panic!("{}", red!("frame pointer is NULL!"));
+        return Err(Error::NullPointer("frame data"));
     }
+    Ok(())
 }
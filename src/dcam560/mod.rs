@@ -2,5 +2,45 @@
 
 const SESSION_INDEX: u32 = 0;
 
+pub mod calibration;
 pub mod device;
+pub mod filter;
 pub mod frame;
+pub mod sdk;
+pub mod sync;
+pub mod wdr;
+
+/// Status messages from numeric code.
+pub(crate) const fn get_message(code: i32) -> &'static str {
+    match code {
+        0 => "PsRetOK",                         // The function completed successfully.
+        -1 => "PsRetNoDeviceConnected", // There is no depth camera connected or the camera has not been connected correctly.
+        -2 => "PsRetInvalidDeviceIndex", // The input device index is invalid.
+        -3 => "PsRetDevicePointerIsNull", // The device structure pointer is null.
+        -4 => "PsRetInvalidFrameType",  // The input frame type is invalid.
+        -5 => "PsRetFramePointerIsNull", // The output frame buffer is null.
+        -6 => "PsRetNoPropertyValueGet", // Cannot get the value for the specified property.
+        -7 => "PsRetNoPropertyValueSet", // Cannot set the value for the specified property.
+        -8 => "PsRetPropertyPointerIsNull", // The input property value buffer pointer is null.
+        -9 => "PsRetPropertySizeNotEnough", // The input property value buffer size is too small to store the specified property value.
+        -10 => "PsRetInvalidDepthRange",    // The input depth range mode is invalid.
+        -11 => "PsRetReadNextFrameTimeOut", // Capture the next image frame time out.
+        -12 => "PsRetInputPointerIsNull",   // An input pointer parameter is null.
+        -13 => "PsRetCameraNotOpened",      // The camera has not been opened.
+        -14 => "PsRetInvalidCameraType",    // The specified type of camera is invalid.
+        -15 => "PsRetInvalidParams", // One or more of the parameter values provided are invalid.
+        -16 => "PsRetCurrentVersionNotSupport", // This feature is not supported in the current version.
+        -17 => "PsRetUpgradeImgError",          // There is an error in the upgrade file.
+        -18 => "PsRetUpgradeImgPathTooLong",    // Upgrade file path length greater than 260.
+        -19 => "PsRetUpgradeCallbackNotSet",    // Ps2_SetUpgradeStatusCallback is not called.
+        -100 => "PsRetNoAdapterConnected",      // There is no adapter connected.
+        -101 => "PsRetReInitialized",           // The SDK has been Initialized.
+        -102 => "PsRetNoInitialized",           // The SDK has not been Initialized.
+        -103 => "PsRetCameraOpened",            // The camera has been opened.
+        -104 => "PsRetCmdError",                // Set/Get cmd control error.
+        -105 => "PsRetCmdSyncTimeOut",          // Set cmd ok.but time out for the sync return.
+        -106 => "PsRetIPNotMatch",              // IP is not in the same network segment.
+        -255 => "PsRetOthers",                  // An unknown error occurred.
+        _ => "_",
+    }
+}
@@ -0,0 +1,103 @@
+//! Slave mode and synchronization of several DCAM560 cameras. Cameras facing the same area disturb each other's depth measurement with their ToF illumination. In slave mode a camera only captures when triggered, so a `TriggerGroup` can trigger the cameras one after another and only one of them illuminates at a time.
+
+use sys::PsReturnStatus_PsRetOK as OK;
+use vzense_sys::dcam560 as sys;
+
+use crate::{Error, frame::FrameSet, red};
+
+use super::{SESSION_INDEX, device::Device, frame};
+
+impl Device {
+    /// Enables or disables slave mode. In slave mode the camera does not stream continuously but captures a frame for each `slave_trigger()`.
+    pub fn set_slave_mode(&self, is_enabled: bool) -> Result<(), Error> {
+        let status =
+            unsafe { sys::Ps2_SetSlaveModeEnabled(self.handle, SESSION_INDEX, is_enabled as u8) };
+        if status != OK {
+            return Err(Error::dcam560("set slave mode enabled", status));
+        }
+        Ok(())
+    }
+
+    /// Triggers a single capture in slave mode. The frames are read as usual with `read_next_frame()`.
+    pub fn slave_trigger(&self) -> Result<(), Error> {
+        let status = unsafe { sys::Ps2_SetSlaveTrigger(self.handle, SESSION_INDEX) };
+        if status != OK {
+            return Err(Error::dcam560("set slave trigger", status));
+        }
+        Ok(())
+    }
+
+    /// Triggers a single capture in slave mode and returns the resulting frames. The waiting time is set with `set_wait_time()`.
+    pub fn trigger_once(&mut self) -> Result<FrameSet, Error> {
+        self.slave_trigger()?;
+        frame::read_frame_set(self)
+    }
+
+    /// Enables or disables the synchronize feature of the API, which synchronizes the capture of the camera with the sync signal of its connector.
+    pub fn set_synchronize(&self, is_enabled: bool) -> Result<(), Error> {
+        let status =
+            unsafe { sys::Ps2_SetSynchronizeEnabled(self.handle, SESSION_INDEX, is_enabled as u8) };
+        if status != OK {
+            return Err(Error::dcam560("set synchronize enabled", status));
+        }
+        Ok(())
+    }
+
+    /// Returns `true` if the synchronize feature is enabled.
+    pub fn is_synchronize_enabled(&self) -> Result<bool, Error> {
+        let mut is_enabled = 0;
+        let status =
+            unsafe { sys::Ps2_GetSynchronizeEnabled(self.handle, SESSION_INDEX, &mut is_enabled) };
+        if status != OK {
+            return Err(Error::dcam560("get synchronize enabled", status));
+        }
+        Ok(is_enabled != 0)
+    }
+}
+
+/// A group of cameras in slave mode which are triggered one after another by `capture()`. The next camera is only triggered after the frames of the previous one were read, so the ToF illumination of the cameras never overlaps.
+pub struct TriggerGroup {
+    devices: Vec<Device>,
+}
+impl TriggerGroup {
+    /// Puts all `devices` into slave mode. If this fails for one of them, the devices already switched are put back into streaming mode and all devices are returned together with the error.
+    pub fn new(devices: Vec<Device>) -> Result<Self, (Error, Vec<Device>)> {
+        for (i, device) in devices.iter().enumerate() {
+            if let Err(e) = device.set_slave_mode(true) {
+                for device in &devices[..i] {
+                    let _ = device.set_slave_mode(false);
+                }
+                return Err((e, devices));
+            }
+        }
+        Ok(Self { devices })
+    }
+
+    /// Triggers the devices one after another and returns the result of each device in the order of the devices. A failing device does not keep the following devices from being triggered.
+    pub fn capture(&mut self) -> Vec<Result<FrameSet, Error>> {
+        self.devices
+            .iter_mut()
+            .map(|device| device.trigger_once())
+            .collect()
+    }
+
+    /// The devices of the group.
+    pub fn devices(&self) -> &[Device] {
+        &self.devices
+    }
+
+    /// The devices of the group, e.g. to change settings. Slave mode must stay enabled.
+    pub fn devices_mut(&mut self) -> &mut [Device] {
+        &mut self.devices
+    }
+
+    /// Disables slave mode on all devices and returns them. Devices which cannot be switched back are still returned, the error is printed.
+    pub fn into_devices(self) -> Vec<Device> {
+        for device in &self.devices {
+            if let Err(e) = device.set_slave_mode(false) {
+                println!("{}", red!("{}", e));
+            }
+        }
+        self.devices
+    }
+}
@@ -0,0 +1,310 @@
+//! Wide dynamic range (WDR) mode, which alternates between two or three depth measuring ranges to cover near and far objects without switching `DepthMeasuringRange` manually.
+
+use sys::PsReturnStatus_PsRetOK as OK;
+use vzense_sys::dcam560 as sys;
+
+use crate::{DepthMeasuringRange, Error};
+
+use super::{SESSION_INDEX, device::Device};
+
+/// How the frames of the different ranges are output.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum WdrStyle {
+    /// The frames of all ranges are fused into one depth frame.
+    Fusion,
+    /// The frames of the ranges are output one after another.
+    Alternation,
+}
+
+/// The frames output in WDR mode.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum WdrData {
+    Depth,
+    Ir,
+    DepthAndIr,
+}
+
+/// Builder for the WDR configuration of a DCAM560. At least two and at most three ranges have to be added with `range()`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct WdrConfig {
+    style: WdrStyle,
+    data: WdrData,
+    ranges: Vec<(DepthMeasuringRange, u8)>,
+    pulse_counts: Vec<u16>,
+    confidence_thresholds: Vec<u16>,
+}
+impl Default for WdrConfig {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+impl WdrConfig {
+    /// A configuration with fused depth output and no ranges yet.
+    pub fn new() -> Self {
+        WdrConfig {
+            style: WdrStyle::Fusion,
+            data: WdrData::Depth,
+            ranges: Vec::new(),
+            pulse_counts: Vec::new(),
+            confidence_thresholds: Vec::new(),
+        }
+    }
+
+    /// Sets whether the ranges are fused or alternated, default is `WdrStyle::Fusion`.
+    pub fn style(mut self, style: WdrStyle) -> Self {
+        self.style = style;
+        self
+    }
+
+    /// Sets the frames to output, default is `WdrData::Depth`.
+    pub fn data(mut self, data: WdrData) -> Self {
+        self.data = data;
+        self
+    }
+
+    /// Adds a range with `frame_count` successive frames captured in this range.
+    pub fn range(mut self, range: DepthMeasuringRange, frame_count: u8) -> Self {
+        self.ranges.push((range, frame_count));
+        self
+    }
+
+    /// Sets the laser pulse count of each range. If not set, the pulse counts of the device are kept.
+    pub fn pulse_counts(mut self, pulse_counts: &[u16]) -> Self {
+        self.pulse_counts = pulse_counts.to_vec();
+        self
+    }
+
+    /// Sets the confidence filter threshold (0 to 1000) of each range. If not set, the thresholds of the device are kept.
+    pub fn confidence_thresholds(mut self, thresholds: &[u16]) -> Self {
+        self.confidence_thresholds = thresholds.to_vec();
+        self
+    }
+
+    /// Checks the configuration, which is also done by `Device::set_wdr()`.
+    pub fn validate(&self) -> Result<(), Error> {
+        if !(2..=3).contains(&self.ranges.len()) {
+            return Err(Error::InvalidParameter("WDR needs two or three ranges"));
+        }
+        if self.ranges.iter().any(|(_, count)| *count == 0) {
+            return Err(Error::InvalidParameter(
+                "frame count of a WDR range must be at least 1",
+            ));
+        }
+        if !self.pulse_counts.is_empty() && self.pulse_counts.len() != self.ranges.len() {
+            return Err(Error::InvalidParameter(
+                "number of pulse counts does not match the number of ranges",
+            ));
+        }
+        if !self.confidence_thresholds.is_empty()
+            && self.confidence_thresholds.len() != self.ranges.len()
+        {
+            return Err(Error::InvalidParameter(
+                "number of confidence thresholds does not match the number of ranges",
+            ));
+        }
+        if self.confidence_thresholds.iter().any(|t| *t > 1000) {
+            return Err(Error::InvalidParameter(
+                "confidence threshold must be in the range 0 to 1000",
+            ));
+        }
+        Ok(())
+    }
+}
+
+impl Device {
+    /// Switches the data mode to WDR and applies `config`. With `WdrStyle::Fusion`, the depth getters like `depth_frame()` return the fused WDR depth frame. The previous data mode is restored by `disable_wdr()`.
+    pub fn set_wdr(&mut self, config: &WdrConfig) -> Result<(), Error> {
+        config.validate()?;
+
+        if self.data_mode_before_wdr.is_none() {
+            let mut data_mode = sys::PsDataMode::default();
+            let status =
+                unsafe { sys::Ps2_GetDataMode(self.handle, SESSION_INDEX, &mut data_mode) };
+            if status != OK {
+                return Err(Error::dcam560("get data mode", status));
+            }
+            self.data_mode_before_wdr = Some(data_mode);
+        }
+
+        let data_mode = match config.data {
+            WdrData::Depth => sys::PsDataMode_PsWDR_Depth,
+            WdrData::Ir => sys::PsDataMode_PsWDR_IR,
+            WdrData::DepthAndIr => sys::PsDataMode_PsWDR_DepthAndIR,
+        };
+        let status = unsafe { sys::Ps2_SetDataMode(self.handle, SESSION_INDEX, data_mode) };
+        if status != OK {
+            return Err(Error::dcam560("set data mode", status));
+        }
+
+        // the third range only takes effect with three ranges
+        let (range3, range3_count) = config.ranges[config.ranges.len() - 1];
+        let mut output_mode = sys::PsWDROutputMode {
+            totalRange: config.ranges.len() as sys::PsWDRTotalRange,
+            range1: to_sys_range(config.ranges[0].0),
+            range1Count: config.ranges[0].1,
+            range2: to_sys_range(config.ranges[1].0),
+            range2Count: config.ranges[1].1,
+            range3: to_sys_range(range3),
+            range3Count: range3_count,
+        };
+        let status =
+            unsafe { sys::Ps2_SetWDROutputMode(self.handle, SESSION_INDEX, &mut output_mode) };
+        if status != OK {
+            return Err(Error::dcam560("set WDR output mode", status));
+        }
+
+        let style = match config.style {
+            WdrStyle::Fusion => sys::PsWDRStyle_PsWDR_FUSION,
+            WdrStyle::Alternation => sys::PsWDRStyle_PsWDR_ALTERNATION,
+        };
+        let status = unsafe { sys::Ps2_SetWDRStyle(self.handle, SESSION_INDEX, style) };
+        if status != OK {
+            return Err(Error::dcam560("set WDR style", status));
+        }
+
+        if let [first, second, rest @ ..] = &config.pulse_counts[..] {
+            // keeps `option` as set on the device
+            let mut pulse_count = sys::PsWDRPulseCount::default();
+            let status =
+                unsafe { sys::Ps2_GetWDRPulseCount(self.handle, SESSION_INDEX, &mut pulse_count) };
+            if status != OK {
+                return Err(Error::dcam560("get WDR pulse count", status));
+            }
+            pulse_count.pulseCount1 = *first;
+            pulse_count.pulseCount2 = *second;
+            pulse_count.pulseCount3 = *rest.first().unwrap_or(second);
+            let status =
+                unsafe { sys::Ps2_SetWDRPulseCount(self.handle, SESSION_INDEX, pulse_count) };
+            if status != OK {
+                return Err(Error::dcam560("set WDR pulse count", status));
+            }
+        }
+
+        if let [first, second, rest @ ..] = &config.confidence_thresholds[..] {
+            let threshold = sys::PsWDRConfidenceThreshold {
+                threshold1: *first,
+                threshold2: *second,
+                threshold3: *rest.first().unwrap_or(second),
+            };
+            let status = unsafe {
+                sys::Ps2_SetWDRConfidenceFilterThreshold(self.handle, SESSION_INDEX, threshold)
+            };
+            if status != OK {
+                return Err(Error::dcam560(
+                    "set WDR confidence filter threshold",
+                    status,
+                ));
+            }
+        }
+        Ok(())
+    }
+
+    /// Leaves WDR mode by switching back to the data mode set before `set_wdr()`, or to the default data mode (depth and RGB at 30 fps) if WDR was not enabled by `set_wdr()`.
+    pub fn disable_wdr(&mut self) -> Result<(), Error> {
+        let data_mode = self
+            .data_mode_before_wdr
+            .unwrap_or(sys::PsDataMode_PsDepthAndRGB_30);
+        let status = unsafe { sys::Ps2_SetDataMode(self.handle, SESSION_INDEX, data_mode) };
+        if status != OK {
+            return Err(Error::dcam560("set data mode", status));
+        }
+        self.data_mode_before_wdr = None;
+        Ok(())
+    }
+
+    /// Returns the laser pulse counts of the three WDR ranges.
+    pub fn get_wdr_pulse_counts(&self) -> Result<[u16; 3], Error> {
+        let mut pulse_count = sys::PsWDRPulseCount::default();
+        let status =
+            unsafe { sys::Ps2_GetWDRPulseCount(self.handle, SESSION_INDEX, &mut pulse_count) };
+        if status != OK {
+            return Err(Error::dcam560("get WDR pulse count", status));
+        }
+        Ok([
+            pulse_count.pulseCount1,
+            pulse_count.pulseCount2,
+            pulse_count.pulseCount3,
+        ])
+    }
+
+    /// Returns the confidence filter thresholds of the three WDR ranges.
+    pub fn get_wdr_confidence_thresholds(&self) -> Result<[u16; 3], Error> {
+        let mut threshold = sys::PsWDRConfidenceThreshold::default();
+        let status = unsafe {
+            sys::Ps2_GetWDRConfidenceFilterThreshold(self.handle, SESSION_INDEX, &mut threshold)
+        };
+        if status != OK {
+            return Err(Error::dcam560(
+                "get WDR confidence filter threshold",
+                status,
+            ));
+        }
+        Ok([
+            threshold.threshold1,
+            threshold.threshold2,
+            threshold.threshold3,
+        ])
+    }
+}
+
+fn to_sys_range(range: DepthMeasuringRange) -> sys::PsDepthRange {
+    match range {
+        DepthMeasuringRange::Near => sys::PsDepthRange_PsNearRange,
+        DepthMeasuringRange::Mid => sys::PsDepthRange_PsMidRange,
+        DepthMeasuringRange::Far => sys::PsDepthRange_PsFarRange,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use DepthMeasuringRange::{Far, Mid, Near};
+
+    fn two_ranges() -> WdrConfig {
+        WdrConfig::new().range(Near, 1).range(Far, 1)
+    }
+
+    #[test]
+    fn range_count() {
+        assert!(WdrConfig::new().range(Near, 1).validate().is_err());
+        assert!(two_ranges().validate().is_ok());
+        assert!(two_ranges().range(Mid, 1).validate().is_ok());
+        let four_ranges = two_ranges().range(Mid, 1).range(Far, 1);
+        assert!(four_ranges.validate().is_err());
+    }
+
+    #[test]
+    fn frame_count() {
+        assert!(
+            WdrConfig::new()
+                .range(Near, 0)
+                .range(Far, 1)
+                .validate()
+                .is_err()
+        );
+        assert!(
+            WdrConfig::new()
+                .range(Near, 255)
+                .range(Far, 1)
+                .validate()
+                .is_ok()
+        );
+    }
+
+    #[test]
+    fn confidence_thresholds() {
+        let config = |thresholds: &[u16]| two_ranges().confidence_thresholds(thresholds);
+        assert!(config(&[0, 0]).validate().is_ok());
+        assert!(config(&[1000, 1000]).validate().is_ok());
+        assert!(config(&[1000, 1001]).validate().is_err());
+        assert!(config(&[500]).validate().is_err());
+        assert!(config(&[500, 500, 500]).validate().is_err());
+    }
+
+    #[test]
+    fn pulse_counts() {
+        assert!(two_ranges().pulse_counts(&[10, 20]).validate().is_ok());
+        assert!(two_ranges().pulse_counts(&[10]).validate().is_err());
+        assert!(two_ranges().pulse_counts(&[10, 20, 30]).validate().is_err());
+    }
+}
@@ -0,0 +1,47 @@
+//! Information about connected devices, used to enumerate devices and to choose one of several.
+
+use std::{ffi::CStr, os::raw::c_char};
+
+use crate::camera::Backend;
+
+/// Connection status of a device as reported by the API.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ConnectStatus {
+    /// The device was found but cannot be reached (`SC_LIMBO` for Scepter, `Unconnected` for DCAM560).
+    Limbo,
+    /// The device can be opened (`SC_CONNECTABLE` for Scepter, `Connected` for DCAM560).
+    Connectable,
+    /// The device is already opened and cannot be opened again.
+    Opened,
+    /// The status is unknown.
+    Unknown,
+}
+
+/// Information about a device found by `list_devices()`. Pass it to `Device::open()` to open this particular device.
+#[derive(Clone, Debug, PartialEq)]
+pub struct DeviceInfo {
+    /// The API the device is used with.
+    pub backend: Backend,
+    /// The product name, e.g. "NYX650" or "DCAM560CPRO".
+    pub model: String,
+    /// The serial number (called alias by the DCAM560 API).
+    pub serial: String,
+    pub ip: String,
+    pub status: ConnectStatus,
+}
+
+/// Events delivered when a device is plugged in or out, see `Sdk::on_hot_plug()` of the respective API.
+#[derive(Clone, Debug, PartialEq)]
+pub enum HotPlugEvent {
+    DeviceAdded(DeviceInfo),
+    DeviceRemoved(DeviceInfo),
+}
+
+/// Converts a null-terminated C string stored in a fixed size array into a `String`.
+pub(crate) fn string_from_c_chars(chars: &[c_char]) -> String {
+    let bytes: Vec<u8> = chars.iter().map(|c| *c as u8).collect();
+    match CStr::from_bytes_until_nul(&bytes) {
+        Ok(s) => s.to_string_lossy().into_owned(),
+        Err(_) => String::from_utf8_lossy(&bytes).into_owned(),
+    }
+}
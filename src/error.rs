@@ -0,0 +1,82 @@
+//! The error type returned by all fallible functions of the library.
+
+use std::fmt;
+
+/// Errors returned by the library. Status codes of the APIs are kept as `ScStatus` (Scepter) or `PsReturnStatus` (DCAM560), except for timeouts and disconnects which have their own variants to be easily distinguished.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Error {
+    /// No frame was ready within the waiting time (`GET_FRAME_READY_TIME_OUT` for Scepter, `PsRetReadNextFrameTimeOut` for DCAM560).
+    Timeout,
+    /// The connection to the device was lost (`DEVICE_IS_LIMBO` for Scepter, `PsRetNoDeviceConnected` for DCAM560).
+    Disconnected,
+    /// No device was found within the scan time.
+    NoDeviceFound,
+    /// The API returned a null pointer for the named object.
+    NullPointer(&'static str),
+    /// A parameter passed to the library was invalid, the reason is given.
+    InvalidParameter(&'static str),
+    /// The named feature is not available with the API of the device.
+    Unsupported(&'static str),
+    /// The Scepter API call `action` failed with a status code.
+    Scepter { action: &'static str, status: i32 },
+    /// The DCAM560 API call `action` failed with a status code.
+    Dcam560 { action: &'static str, status: i32 },
+}
+impl Error {
+    /// Creates an error from a non-OK Scepter status.
+    pub(crate) fn scepter(action: &'static str, status: i32) -> Self {
+        match status {
+            -1 => Error::Disconnected,
+            -11 => Error::Timeout,
+            _ => Error::Scepter { action, status },
+        }
+    }
+
+    /// Creates an error from a non-OK DCAM560 status.
+    pub(crate) fn dcam560(action: &'static str, status: i32) -> Self {
+        match status {
+            -1 => Error::Disconnected,
+            -11 => Error::Timeout,
+            _ => Error::Dcam560 { action, status },
+        }
+    }
+
+    /// The status code returned by the API, if the error originates from an API call.
+    pub fn status(&self) -> Option<i32> {
+        match self {
+            Error::Timeout => Some(-11),
+            Error::Disconnected => Some(-1),
+            Error::Scepter { status, .. } | Error::Dcam560 { status, .. } => Some(*status),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::Timeout => write!(f, "timed out waiting for the next frame"),
+            Error::Disconnected => write!(f, "the connection to the device was lost"),
+            Error::NoDeviceFound => write!(f, "no device found"),
+            Error::NullPointer(name) => write!(f, "{} is null", name),
+            Error::InvalidParameter(reason) => write!(f, "invalid parameter: {}", reason),
+            Error::Unsupported(feature) => write!(f, "{} is not supported", feature),
+            Error::Scepter { action, status } => write!(
+                f,
+                "{} failed with status {} ({})",
+                action,
+                crate::scepter::get_message(*status),
+                status
+            ),
+            Error::Dcam560 { action, status } => write!(
+                f,
+                "{} failed with status {} ({})",
+                action,
+                crate::dcam560::get_message(*status),
+                status
+            ),
+        }
+    }
+}
+
+impl std::error::Error for Error {}
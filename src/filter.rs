@@ -0,0 +1,150 @@
+//! Configuration of the depth filters running on the camera, shared by both APIs.
+
+use crate::{Error, camera::Backend};
+
+/// Configuration of the depth filters running on the camera. When applying the configuration, `None` leaves the setting of the device unchanged. When reading it back, `None` means the setting is not available with the API of the device.
+///
+/// | setting | Scepter | DCAM560 |
+/// |---|---|---|
+/// | `time_filter` | yes | yes |
+/// | `time_filter_threshold` | 1 to 6 | - |
+/// | `confidence_filter` | yes | yes |
+/// | `confidence_threshold` | 1 to 100 | 0 to 1000 |
+/// | `flying_pixel_filter` | yes | - |
+/// | `flying_pixel_threshold` | 1 to 16 | - |
+/// | `spatial_filter` | yes | yes |
+/// | `fill_hole_filter` | yes | - |
+///
+/// For all thresholds, larger values filter more strongly.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct DepthFilterConfig {
+    /// Smooths the depth over time, reducing the wobble of the point cloud.
+    pub time_filter: Option<bool>,
+    pub time_filter_threshold: Option<u8>,
+    /// Removes pixels with low confidence.
+    pub confidence_filter: Option<bool>,
+    pub confidence_threshold: Option<u16>,
+    /// Removes pixels at edges which belong neither to the foreground nor to the background.
+    pub flying_pixel_filter: Option<bool>,
+    pub flying_pixel_threshold: Option<u8>,
+    /// Smooths the depth spatially.
+    pub spatial_filter: Option<bool>,
+    /// Fills small holes in the depth frame.
+    pub fill_hole_filter: Option<bool>,
+}
+impl DepthFilterConfig {
+    /// Checks that all settings are available with the API of `backend` and within their valid ranges. Returns `Error::Unsupported` or `Error::InvalidParameter` otherwise.
+    pub fn validate(&self, backend: Backend) -> Result<(), Error> {
+        match backend {
+            Backend::Scepter => {
+                check_range(self.time_filter_threshold, 1, 6, "time filter threshold")?;
+                check_range(self.confidence_threshold, 1, 100, "confidence threshold")?;
+                check_range(self.flying_pixel_threshold, 1, 16, "flying pixel threshold")?;
+            }
+            Backend::Dcam560 => {
+                if self.time_filter_threshold.is_some() {
+                    return Err(Error::Unsupported("time filter threshold"));
+                }
+                if self.flying_pixel_filter.is_some() || self.flying_pixel_threshold.is_some() {
+                    return Err(Error::Unsupported("flying pixel filter"));
+                }
+                if self.fill_hole_filter.is_some() {
+                    return Err(Error::Unsupported("fill hole filter"));
+                }
+                check_range(self.confidence_threshold, 0, 1000, "confidence threshold")?;
+            }
+        }
+        Ok(())
+    }
+}
+
+fn check_range<T: PartialOrd>(
+    value: Option<T>,
+    min: T,
+    max: T,
+    name: &'static str,
+) -> Result<(), Error> {
+    match value {
+        Some(v) if v < min || v > max => Err(Error::InvalidParameter(name)),
+        _ => Ok(()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn is_valid(config: DepthFilterConfig, backend: Backend) -> bool {
+        config.validate(backend).is_ok()
+    }
+
+    #[test]
+    fn scepter_thresholds() {
+        let time = |t| DepthFilterConfig {
+            time_filter_threshold: Some(t),
+            ..Default::default()
+        };
+        assert!(!is_valid(time(0), Backend::Scepter));
+        assert!(is_valid(time(1), Backend::Scepter));
+        assert!(is_valid(time(6), Backend::Scepter));
+        assert!(!is_valid(time(7), Backend::Scepter));
+
+        let confidence = |t| DepthFilterConfig {
+            confidence_threshold: Some(t),
+            ..Default::default()
+        };
+        assert!(!is_valid(confidence(0), Backend::Scepter));
+        assert!(is_valid(confidence(1), Backend::Scepter));
+        assert!(is_valid(confidence(100), Backend::Scepter));
+        assert!(!is_valid(confidence(101), Backend::Scepter));
+
+        let flying_pixel = |t| DepthFilterConfig {
+            flying_pixel_threshold: Some(t),
+            ..Default::default()
+        };
+        assert!(!is_valid(flying_pixel(0), Backend::Scepter));
+        assert!(is_valid(flying_pixel(1), Backend::Scepter));
+        assert!(is_valid(flying_pixel(16), Backend::Scepter));
+        assert!(!is_valid(flying_pixel(17), Backend::Scepter));
+    }
+
+    #[test]
+    fn dcam560_thresholds() {
+        let confidence = |t| DepthFilterConfig {
+            confidence_threshold: Some(t),
+            ..Default::default()
+        };
+        assert!(is_valid(confidence(0), Backend::Dcam560));
+        assert!(is_valid(confidence(1000), Backend::Dcam560));
+        assert!(matches!(
+            confidence(1001).validate(Backend::Dcam560),
+            Err(Error::InvalidParameter(_))
+        ));
+    }
+
+    #[test]
+    fn dcam560_unsupported() {
+        let unsupported = [
+            DepthFilterConfig {
+                time_filter_threshold: Some(1),
+                ..Default::default()
+            },
+            DepthFilterConfig {
+                flying_pixel_filter: Some(false),
+                ..Default::default()
+            },
+            DepthFilterConfig {
+                fill_hole_filter: Some(true),
+                ..Default::default()
+            },
+        ];
+        for config in unsupported {
+            assert!(matches!(
+                config.validate(Backend::Dcam560),
+                Err(Error::Unsupported(_))
+            ));
+            assert!(is_valid(config, Backend::Scepter));
+        }
+        assert!(is_valid(DepthFilterConfig::default(), Backend::Dcam560));
+    }
+}
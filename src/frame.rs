@@ -0,0 +1,187 @@
+//! Owned frames and zero-copy frame views carrying the image data together with resolution, pixel format, and metadata of the capture.
+
+use std::time::Instant;
+
+use crate::Resolution;
+
+/// Pixel formats of the frame data.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum PixelFormat {
+    /// Depth in mm, 16 bits per pixel.
+    DepthMm16,
+    /// Gray image, 16 bits per pixel.
+    Gray16,
+    /// Gray image, 8 bits per pixel.
+    Gray8,
+    /// Color image, 24 bits per pixel in RGB order.
+    Rgb888,
+    /// Color image, 24 bits per pixel in BGR order.
+    Bgr888,
+    /// Color image, 16 bits per pixel in RGB order.
+    Rgb565,
+    /// Color image, 16 bits per pixel in BGR order.
+    Bgr565,
+    /// JPEG compressed color image, the data holds the compressed bytes instead of pixels.
+    Jpeg,
+    /// A format code unknown to this library.
+    Unknown(u32),
+}
+
+/// Metadata which is only provided by one of the APIs.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum FrameMetadata {
+    /// Metadata of a Scepter frame.
+    Scepter {
+        /// The timestamp in ms when the frame was generated on the device. Frame processing and transfer time are not included. If the device clock is synchronized by NTP or PTP (see `scepter::time_sync`), the timestamp counts from the Unix epoch, otherwise see `clock::DriftEstimator`.
+        device_timestamp: u64,
+        /// The number of sub-frames with different exposure times fused into a depth or IR frame, 1 unless HDR or WDR mode is enabled (see `scepter::hdr`).
+        sub_frame_count: u8,
+    },
+    /// Metadata of a DCAM560 frame.
+    Dcam560 {
+        /// The exposure time in ms.
+        exposure_time: f32,
+        /// The depth range mode (`PsDepthRange`) of the frame. Only used for depth frames.
+        depth_range: i32,
+        /// Used to synchronize with the IMU, in the range of 0 to 255.
+        imu_frame_no: u8,
+        /// The timestamp of the camera (`PsFrame::hardwaretimestamp`). `Vzense_types_560.h` does not document its unit, see `clock::DriftEstimator` for what this means for the mapping to host time.
+        hardware_timestamp: u64,
+    },
+}
+impl FrameMetadata {
+    /// The timestamp of the device (`device_timestamp` for Scepter, `hardware_timestamp` for DCAM560).
+    pub const fn timestamp(&self) -> u64 {
+        match self {
+            FrameMetadata::Scepter {
+                device_timestamp, ..
+            } => *device_timestamp,
+            FrameMetadata::Dcam560 {
+                hardware_timestamp, ..
+            } => *hardware_timestamp,
+        }
+    }
+}
+
+/// An owned frame. `data` holds `width * height` pixels, color frames hold three consecutive values per pixel.
+#[derive(Clone, Debug)]
+pub struct Frame<T> {
+    pub width: u32,
+    pub height: u32,
+    pub pixel_format: PixelFormat,
+    /// The index of the frame as counted by the device.
+    pub frame_index: u32,
+    pub metadata: FrameMetadata,
+    pub data: Vec<T>,
+}
+impl<T> Frame<T> {
+    /// The resolution of the frame.
+    pub const fn resolution(&self) -> Resolution {
+        Resolution::new(self.width, self.height)
+    }
+
+    /// The timestamp of the device, see `FrameMetadata::timestamp()`.
+    pub const fn timestamp(&self) -> u64 {
+        self.metadata.timestamp()
+    }
+}
+
+/// Depth frame with depth in mm.
+pub type DepthFrame = Frame<u16>;
+
+/// IR frame. The Scepter API provides 8 bit and the DCAM560 API 16 bit intensities, see `pixel_format`.
+pub type IrFrame = Frame<u16>;
+
+/// Color frame with 24 bit stored in consecutive `u8`. If `pixel_format` is `PixelFormat::Jpeg`, `data` holds the compressed image instead.
+pub type ColorFrame = Frame<u8>;
+
+/// A zero-copy view of a frame, borrowing the buffer of the API directly. The borrow of the device ensures that the view cannot be used after the next `read_next_frame()`, which recycles the buffer.
+#[derive(Clone, Copy, Debug)]
+pub struct FrameView<'a> {
+    pub width: u32,
+    pub height: u32,
+    pub pixel_format: PixelFormat,
+    /// The index of the frame as counted by the device.
+    pub frame_index: u32,
+    pub metadata: FrameMetadata,
+    pub(crate) data: &'a [u8],
+}
+impl<'a> FrameView<'a> {
+    /// The resolution of the frame.
+    pub const fn resolution(&self) -> Resolution {
+        Resolution::new(self.width, self.height)
+    }
+
+    /// The frame data as bytes, e.g. 24 bit color stored in consecutive `u8`.
+    pub const fn as_u8(&self) -> &'a [u8] {
+        self.data
+    }
+
+    /// The frame data as `u16`, e.g. depth in mm. Returns `None` if the pixel format has no 16 bit values or the buffer is not aligned to `u16`. Only little-endian targets are supported (like the libraries of the APIs).
+    pub fn as_u16(&self) -> Option<&'a [u16]> {
+        if !matches!(
+            self.pixel_format,
+            PixelFormat::DepthMm16 | PixelFormat::Gray16
+        ) {
+            return None;
+        }
+        // every bit pattern is a valid u16, so reinterpreting aligned bytes is sound
+        let (head, data, tail) = unsafe { self.data.align_to::<u16>() };
+        if head.is_empty() && tail.is_empty() {
+            Some(data)
+        } else {
+            None
+        }
+    }
+}
+
+/// The frames of one capture, returned by `read_frame_set()`. Each frame is `None` if it was not ready in that capture. The frame indices and timestamps of the frames allow to check whether they belong together, see `is_synchronized()`.
+#[derive(Clone, Debug)]
+pub struct FrameSet {
+    pub depth: Option<DepthFrame>,
+    pub ir: Option<IrFrame>,
+    pub color: Option<ColorFrame>,
+    /// The time on the host when the capture was completed by `read_next_frame()`.
+    pub host_time: Instant,
+}
+impl FrameSet {
+    /// `true` if no frame was ready in the capture.
+    pub const fn is_empty(&self) -> bool {
+        self.depth.is_none() && self.ir.is_none() && self.color.is_none()
+    }
+
+    /// The frame indices of depth, IR, and color frame.
+    pub fn frame_indices(&self) -> [Option<u32>; 3] {
+        [
+            self.depth.as_ref().map(|f| f.frame_index),
+            self.ir.as_ref().map(|f| f.frame_index),
+            self.color.as_ref().map(|f| f.frame_index),
+        ]
+    }
+
+    /// The device timestamps of depth, IR, and color frame.
+    pub fn timestamps(&self) -> [Option<u64>; 3] {
+        [
+            self.depth.as_ref().map(|f| f.timestamp()),
+            self.ir.as_ref().map(|f| f.timestamp()),
+            self.color.as_ref().map(|f| f.timestamp()),
+        ]
+    }
+
+    /// The largest difference between the device timestamps of the frames in the set. `None` if the set has less than two frames.
+    pub fn max_timestamp_difference(&self) -> Option<u64> {
+        let timestamps = self.timestamps();
+        let mut present = timestamps.iter().flatten();
+        let first = *present.next()?;
+        let (min, max, count) = present.fold((first, first, 1), |(min, max, count), t| {
+            (min.min(*t), max.max(*t), count + 1)
+        });
+        (count > 1).then_some(max - min)
+    }
+
+    /// `true` if the device timestamps of all frames in the set differ by at most `tolerance`. A set with less than two frames is always synchronized.
+    pub fn is_synchronized(&self, tolerance: u64) -> bool {
+        self.max_timestamp_difference()
+            .is_none_or(|difference| difference <= tolerance)
+    }
+}
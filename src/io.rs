@@ -0,0 +1,759 @@
+//! Reading and writing point clouds in the PLY (ASCII and binary), PCD, and XYZ formats, e.g. to exchange them with CloudCompare or PCL. Colors and IR intensities are written if the point cloud has them. Only points with depth are written, so point clouds read back are unorganized (`height == 1`), except for organized PCD files written by other tools.
+
+use std::{
+    fs::File,
+    io::{self, BufRead, BufReader, BufWriter, Write},
+    path::Path,
+};
+
+use crate::point_cloud::PointCloud;
+
+/// Encoding of the point data in PLY and PCD files.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Encoding {
+    Ascii,
+    /// Little-endian binary data.
+    Binary,
+}
+
+/// Writes `cloud` to a PLY file at `path`.
+pub fn write_ply(path: impl AsRef<Path>, cloud: &PointCloud, encoding: Encoding) -> io::Result<()> {
+    write_ply_to(BufWriter::new(File::create(path)?), cloud, encoding)
+}
+
+/// Writes `cloud` in the PLY format to `writer`. The coordinates are stored as `float`, colors as `uchar` `red`, `green`, and `blue`, and IR intensities as `ushort` `intensity`.
+pub fn write_ply_to(
+    mut writer: impl Write,
+    cloud: &PointCloud,
+    encoding: Encoding,
+) -> io::Result<()> {
+    check_lengths(cloud)?;
+    let indices = valid_indices(cloud);
+    let format = match encoding {
+        Encoding::Ascii => "ascii",
+        Encoding::Binary => "binary_little_endian",
+    };
+    writeln!(writer, "ply")?;
+    writeln!(writer, "format {} 1.0", format)?;
+    writeln!(writer, "comment written by vzense-rust")?;
+    writeln!(writer, "element vertex {}", indices.len())?;
+    for axis in ["x", "y", "z"] {
+        writeln!(writer, "property float {}", axis)?;
+    }
+    if cloud.colors.is_some() {
+        for channel in ["red", "green", "blue"] {
+            writeln!(writer, "property uchar {}", channel)?;
+        }
+    }
+    if cloud.intensities.is_some() {
+        writeln!(writer, "property ushort intensity")?;
+    }
+    writeln!(writer, "end_header")?;
+
+    for i in indices {
+        match encoding {
+            Encoding::Ascii => {
+                write_text_point(&mut writer, cloud, i)?;
+            }
+            Encoding::Binary => {
+                for c in cloud.points[i] {
+                    writer.write_all(&c.to_le_bytes())?;
+                }
+                if let Some(colors) = &cloud.colors {
+                    writer.write_all(&colors[i])?;
+                }
+                if let Some(intensities) = &cloud.intensities {
+                    writer.write_all(&intensities[i].to_le_bytes())?;
+                }
+            }
+        }
+    }
+    writer.flush()
+}
+
+/// Reads a point cloud from a PLY file at `path`.
+pub fn read_ply(path: impl AsRef<Path>) -> io::Result<PointCloud> {
+    read_ply_from(BufReader::new(File::open(path)?))
+}
+
+/// Reads a point cloud in the PLY format from `reader`. The vertices need the properties `x`, `y`, and `z`. `red`, `green`, and `blue` are read as colors and `intensity` as IR intensity, other properties and elements are ignored. Only ASCII and little-endian binary files are supported.
+pub fn read_ply_from(mut reader: impl BufRead) -> io::Result<PointCloud> {
+    if read_header_line(&mut reader)? != "ply" {
+        return Err(invalid_data("missing PLY magic number"));
+    }
+    let mut encoding = None;
+    let mut vertex_count = None;
+    let mut fields = Vec::new();
+    let mut element = String::new();
+    loop {
+        let line = read_header_line(&mut reader)?;
+        let tokens: Vec<&str> = line.split_whitespace().collect();
+        match tokens.as_slice() {
+            ["end_header"] => break,
+            ["format", "ascii", _] => encoding = Some(Encoding::Ascii),
+            ["format", "binary_little_endian", _] => encoding = Some(Encoding::Binary),
+            ["format", ..] => return Err(invalid_data("unsupported PLY format")),
+            ["element", name, count] => {
+                if vertex_count.is_none() && *name != "vertex" && *count != "0" {
+                    return Err(invalid_data(
+                        "PLY elements before the vertices are not supported",
+                    ));
+                }
+                if *name == "vertex" {
+                    vertex_count = Some(parse::<usize>(count)?);
+                }
+                element = name.to_string();
+            }
+            ["property", "list", ..] if element == "vertex" => {
+                return Err(invalid_data(
+                    "PLY list properties of vertices are not supported",
+                ));
+            }
+            ["property", kind, name] if element == "vertex" => fields.push(Field {
+                name: name.to_lowercase(),
+                scalar: Scalar::from_ply(kind)?,
+                count: 1,
+            }),
+            _ => {}
+        }
+    }
+    let encoding = encoding.ok_or_else(|| invalid_data("missing PLY format"))?;
+    let vertex_count = vertex_count.ok_or_else(|| invalid_data("missing PLY vertex element"))?;
+    let records = read_records(&mut reader, &fields, vertex_count, encoding)?;
+    to_point_cloud(&fields, &records, unorganized_width(vertex_count)?, 1)
+}
+
+/// Writes `cloud` to a PCD file at `path`.
+pub fn write_pcd(path: impl AsRef<Path>, cloud: &PointCloud, encoding: Encoding) -> io::Result<()> {
+    write_pcd_to(BufWriter::new(File::create(path)?), cloud, encoding)
+}
+
+/// Writes `cloud` in the PCD format of PCL to `writer`. Colors are stored as packed `rgba` like in `pcl::PointXYZRGBA`, IR intensities as float `intensity` like in `pcl::PointXYZI`.
+pub fn write_pcd_to(
+    mut writer: impl Write,
+    cloud: &PointCloud,
+    encoding: Encoding,
+) -> io::Result<()> {
+    check_lengths(cloud)?;
+    let indices = valid_indices(cloud);
+    let (mut fields, mut types) = (vec!["x", "y", "z"], vec!["F", "F", "F"]);
+    if cloud.colors.is_some() {
+        fields.push("rgba");
+        types.push("U");
+    }
+    if cloud.intensities.is_some() {
+        fields.push("intensity");
+        types.push("F");
+    }
+    writeln!(writer, "# .PCD v0.7 - Point Cloud Data file format")?;
+    writeln!(writer, "VERSION 0.7")?;
+    writeln!(writer, "FIELDS {}", fields.join(" "))?;
+    writeln!(writer, "SIZE {}", vec!["4"; fields.len()].join(" "))?;
+    writeln!(writer, "TYPE {}", types.join(" "))?;
+    writeln!(writer, "COUNT {}", vec!["1"; fields.len()].join(" "))?;
+    writeln!(writer, "WIDTH {}", indices.len())?;
+    writeln!(writer, "HEIGHT 1")?;
+    writeln!(writer, "VIEWPOINT 0 0 0 1 0 0 0")?;
+    writeln!(writer, "POINTS {}", indices.len())?;
+    writeln!(
+        writer,
+        "DATA {}",
+        match encoding {
+            Encoding::Ascii => "ascii",
+            Encoding::Binary => "binary",
+        }
+    )?;
+
+    for i in indices {
+        let rgba = cloud
+            .colors
+            .as_ref()
+            .map(|colors| u32::from_be_bytes([255, colors[i][0], colors[i][1], colors[i][2]]));
+        let intensity = cloud.intensities.as_ref().map(|v| v[i] as f32);
+        match encoding {
+            Encoding::Ascii => {
+                let [x, y, z] = cloud.points[i];
+                write!(writer, "{} {} {}", x, y, z)?;
+                if let Some(rgba) = rgba {
+                    write!(writer, " {}", rgba)?;
+                }
+                if let Some(intensity) = intensity {
+                    write!(writer, " {}", intensity)?;
+                }
+                writeln!(writer)?;
+            }
+            Encoding::Binary => {
+                for c in cloud.points[i] {
+                    writer.write_all(&c.to_le_bytes())?;
+                }
+                if let Some(rgba) = rgba {
+                    writer.write_all(&rgba.to_le_bytes())?;
+                }
+                if let Some(intensity) = intensity {
+                    writer.write_all(&intensity.to_le_bytes())?;
+                }
+            }
+        }
+    }
+    writer.flush()
+}
+
+/// Reads a point cloud from a PCD file at `path`.
+pub fn read_pcd(path: impl AsRef<Path>) -> io::Result<PointCloud> {
+    read_pcd_from(BufReader::new(File::open(path)?))
+}
+
+/// Reads a point cloud in the PCD format of PCL from `reader`. Besides `x`, `y`, and `z`, the packed colors `rgb` or `rgba` and `intensity` are read, other fields are ignored. Organized point clouds keep their width and height, with invalid (NaN) points set to zero. Compressed binary data is not supported.
+pub fn read_pcd_from(mut reader: impl BufRead) -> io::Result<PointCloud> {
+    let mut names = Vec::new();
+    let mut sizes = Vec::new();
+    let mut types = Vec::new();
+    let mut counts = Vec::new();
+    let (mut width, mut height, mut points) = (None, 1, None);
+    let encoding = loop {
+        let line = read_header_line(&mut reader)?;
+        let mut tokens = line.split_whitespace();
+        let Some(key) = tokens.next() else {
+            continue;
+        };
+        let values: Vec<&str> = tokens.collect();
+        match key.to_uppercase().as_str() {
+            "FIELDS" => names = values.iter().map(|v| v.to_lowercase()).collect(),
+            "SIZE" => sizes = values.iter().map(|v| parse(v)).collect::<io::Result<_>>()?,
+            "TYPE" => types = values.iter().map(|v| v.to_string()).collect(),
+            "COUNT" => counts = values.iter().map(|v| parse(v)).collect::<io::Result<_>>()?,
+            "WIDTH" => width = Some(parse::<u32>(values.first().unwrap_or(&""))?),
+            "HEIGHT" => height = parse::<u32>(values.first().unwrap_or(&""))?,
+            "POINTS" => points = Some(parse::<usize>(values.first().unwrap_or(&""))?),
+            "DATA" => match values.first().copied() {
+                Some("ascii") => break Encoding::Ascii,
+                Some("binary") => break Encoding::Binary,
+                _ => return Err(invalid_data("unsupported PCD data encoding")),
+            },
+            _ => {}
+        }
+    };
+    if sizes.len() != names.len()
+        || types.len() != names.len()
+        || (!counts.is_empty() && counts.len() != names.len())
+    {
+        return Err(invalid_data("PCD header has inconsistent fields"));
+    }
+    if counts.is_empty() {
+        counts = vec![1; names.len()];
+    }
+    let fields = names
+        .into_iter()
+        .zip(sizes)
+        .zip(types)
+        .zip(counts)
+        .map(|(((name, size), kind), count)| {
+            let packed_color = (name == "rgb" || name == "rgba") && kind == "F";
+            Ok(Field {
+                scalar: if packed_color {
+                    Scalar::PackedColor
+                } else {
+                    Scalar::from_pcd(&kind, size)?
+                },
+                name,
+                count,
+            })
+        })
+        .collect::<io::Result<Vec<_>>>()?;
+    let width = width.ok_or_else(|| invalid_data("missing PCD width"))?;
+    let pixel_count = width
+        .checked_mul(height)
+        .ok_or_else(|| invalid_data("PCD width times height is too large"))?
+        as usize;
+    let points = points.unwrap_or(pixel_count);
+    let records = read_records(&mut reader, &fields, points, encoding)?;
+    if points == pixel_count {
+        to_point_cloud(&fields, &records, width, height)
+    } else {
+        to_point_cloud(&fields, &records, unorganized_width(points)?, 1)
+    }
+}
+
+/// Writes `cloud` to an XYZ file at `path`.
+pub fn write_xyz(path: impl AsRef<Path>, cloud: &PointCloud) -> io::Result<()> {
+    write_xyz_to(BufWriter::new(File::create(path)?), cloud)
+}
+
+/// Writes `cloud` as plain text to `writer`, one point per line with the columns x, y, z, followed by red, green, and blue if the cloud has colors, and the IR intensity if it has intensities.
+pub fn write_xyz_to(mut writer: impl Write, cloud: &PointCloud) -> io::Result<()> {
+    check_lengths(cloud)?;
+    for i in valid_indices(cloud) {
+        write_text_point(&mut writer, cloud, i)?;
+    }
+    writer.flush()
+}
+
+/// Reads a point cloud from an XYZ file at `path`.
+pub fn read_xyz(path: impl AsRef<Path>) -> io::Result<PointCloud> {
+    read_xyz_from(BufReader::new(File::open(path)?))
+}
+
+/// Reads a point cloud in the XYZ format from `reader`. The number of columns determines their meaning: 3 (x, y, z), 4 (x, y, z, intensity), 6 (x, y, z, red, green, blue), or 7 (x, y, z, red, green, blue, intensity). Empty lines and lines starting with `#` or `//` are skipped.
+pub fn read_xyz_from(reader: impl BufRead) -> io::Result<PointCloud> {
+    let mut fields: Option<Vec<Field>> = None;
+    let mut records = Vec::new();
+    for line in reader.lines() {
+        let line = line?;
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') || line.starts_with("//") {
+            continue;
+        }
+        let values = line
+            .split([' ', '\t', ',', ';'])
+            .filter(|token| !token.is_empty())
+            .map(parse::<f64>)
+            .collect::<io::Result<Vec<_>>>()?;
+        let fields = match &fields {
+            Some(fields) => fields,
+            None => fields.insert(xyz_fields(values.len())?),
+        };
+        if values.len() != fields.len() {
+            return Err(invalid_data("XYZ lines have different numbers of columns"));
+        }
+        records.push(values);
+    }
+    let Some(fields) = fields else {
+        return Ok(PointCloud::default());
+    };
+    to_point_cloud(&fields, &records, unorganized_width(records.len())?, 1)
+}
+
+// private functions_______________________________________________________
+
+/// A scalar type of PLY properties and PCD fields.
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Scalar {
+    I8,
+    U8,
+    I16,
+    U16,
+    I32,
+    U32,
+    F32,
+    F64,
+    /// Color packed into the bits of a float, as used by PCL for `rgb`.
+    PackedColor,
+}
+impl Scalar {
+    fn from_ply(name: &str) -> io::Result<Self> {
+        Ok(match name {
+            "char" | "int8" => Scalar::I8,
+            "uchar" | "uint8" => Scalar::U8,
+            "short" | "int16" => Scalar::I16,
+            "ushort" | "uint16" => Scalar::U16,
+            "int" | "int32" => Scalar::I32,
+            "uint" | "uint32" => Scalar::U32,
+            "float" | "float32" => Scalar::F32,
+            "double" | "float64" => Scalar::F64,
+            _ => return Err(invalid_data("unknown PLY property type")),
+        })
+    }
+
+    fn from_pcd(kind: &str, size: usize) -> io::Result<Self> {
+        Ok(match (kind, size) {
+            ("I", 1) => Scalar::I8,
+            ("U", 1) => Scalar::U8,
+            ("I", 2) => Scalar::I16,
+            ("U", 2) => Scalar::U16,
+            ("I", 4) => Scalar::I32,
+            ("U", 4) => Scalar::U32,
+            ("F", 4) => Scalar::F32,
+            ("F", 8) => Scalar::F64,
+            _ => return Err(invalid_data("unsupported PCD field type")),
+        })
+    }
+
+    const fn size(self) -> usize {
+        match self {
+            Scalar::I8 | Scalar::U8 => 1,
+            Scalar::I16 | Scalar::U16 => 2,
+            Scalar::I32 | Scalar::U32 | Scalar::F32 | Scalar::PackedColor => 4,
+            Scalar::F64 => 8,
+        }
+    }
+
+    /// Decodes a little-endian value of `self.size()` bytes.
+    fn decode(self, b: &[u8]) -> f64 {
+        match self {
+            Scalar::I8 => b[0] as i8 as f64,
+            Scalar::U8 => b[0] as f64,
+            Scalar::I16 => i16::from_le_bytes([b[0], b[1]]) as f64,
+            Scalar::U16 => u16::from_le_bytes([b[0], b[1]]) as f64,
+            Scalar::I32 => i32::from_le_bytes([b[0], b[1], b[2], b[3]]) as f64,
+            Scalar::U32 | Scalar::PackedColor => {
+                u32::from_le_bytes([b[0], b[1], b[2], b[3]]) as f64
+            }
+            Scalar::F32 => f32::from_le_bytes([b[0], b[1], b[2], b[3]]) as f64,
+            Scalar::F64 => f64::from_le_bytes([b[0], b[1], b[2], b[3], b[4], b[5], b[6], b[7]]),
+        }
+    }
+
+    fn parse(self, token: &str) -> io::Result<f64> {
+        match self {
+            Scalar::PackedColor => Ok(parse::<f32>(token)?.to_bits() as f64),
+            _ => parse(token),
+        }
+    }
+}
+
+/// A PLY property or PCD field holding `count` scalars.
+#[derive(Clone, Debug)]
+struct Field {
+    name: String,
+    scalar: Scalar,
+    count: usize,
+}
+
+fn xyz_fields(columns: usize) -> io::Result<Vec<Field>> {
+    let names: &[&str] = match columns {
+        3 => &["x", "y", "z"],
+        4 => &["x", "y", "z", "intensity"],
+        6 => &["x", "y", "z", "red", "green", "blue"],
+        7 => &["x", "y", "z", "red", "green", "blue", "intensity"],
+        _ => return Err(invalid_data("XYZ lines need 3, 4, 6, or 7 columns")),
+    };
+    Ok(names
+        .iter()
+        .map(|name| Field {
+            name: name.to_string(),
+            scalar: Scalar::F64,
+            count: 1,
+        })
+        .collect())
+}
+
+/// Reads `count` records, each holding the scalars of all fields one after another. `count` comes from the header, so no memory is reserved for it in advance.
+fn read_records(
+    reader: &mut impl BufRead,
+    fields: &[Field],
+    count: usize,
+    encoding: Encoding,
+) -> io::Result<Vec<Vec<f64>>> {
+    let scalars: Vec<Scalar> = fields
+        .iter()
+        .flat_map(|f| std::iter::repeat_n(f.scalar, f.count))
+        .collect();
+    let mut records = Vec::new();
+    match encoding {
+        Encoding::Ascii => {
+            let mut line = String::new();
+            while records.len() < count {
+                line.clear();
+                if reader.read_line(&mut line)? == 0 {
+                    return Err(invalid_data("file ends before all points were read"));
+                }
+                let tokens: Vec<&str> = line.split_whitespace().collect();
+                if tokens.is_empty() {
+                    continue;
+                }
+                if tokens.len() < scalars.len() {
+                    return Err(invalid_data("point has too few values"));
+                }
+                let record = scalars
+                    .iter()
+                    .zip(tokens)
+                    .map(|(scalar, token)| scalar.parse(token))
+                    .collect::<io::Result<_>>()?;
+                records.push(record);
+            }
+        }
+        Encoding::Binary => {
+            let mut bytes = vec![0; scalars.iter().map(|s| s.size()).sum()];
+            for _ in 0..count {
+                reader.read_exact(&mut bytes).map_err(|e| match e.kind() {
+                    io::ErrorKind::UnexpectedEof => {
+                        invalid_data("file ends before all points were read")
+                    }
+                    _ => e,
+                })?;
+                let mut offset = 0;
+                let record = scalars
+                    .iter()
+                    .map(|scalar| {
+                        let value = scalar.decode(&bytes[offset..]);
+                        offset += scalar.size();
+                        value
+                    })
+                    .collect();
+                records.push(record);
+            }
+        }
+    }
+    Ok(records)
+}
+
+/// Builds a point cloud from records of `fields`. Points with non-finite coordinates are set to zero.
+fn to_point_cloud(
+    fields: &[Field],
+    records: &[Vec<f64>],
+    width: u32,
+    height: u32,
+) -> io::Result<PointCloud> {
+    // index of the first scalar of each field within a record
+    let offset = |names: &[&str]| {
+        let mut offset = 0;
+        for field in fields {
+            if names.contains(&field.name.as_str()) {
+                return Some(offset);
+            }
+            offset += field.count;
+        }
+        None
+    };
+    let (Some(x), Some(y), Some(z)) = (offset(&["x"]), offset(&["y"]), offset(&["z"])) else {
+        return Err(invalid_data("points need x, y, and z coordinates"));
+    };
+    let rgb = match (
+        offset(&["red", "r"]),
+        offset(&["green", "g"]),
+        offset(&["blue", "b"]),
+    ) {
+        (Some(r), Some(g), Some(b)) => Some((r, g, b)),
+        _ => None,
+    };
+    let packed = offset(&["rgb", "rgba"]);
+    let intensity = offset(&["intensity", "scalar_intensity"]);
+
+    let points = records
+        .iter()
+        .map(|r| {
+            let point = [r[x] as f32, r[y] as f32, r[z] as f32];
+            if point.iter().all(|c| c.is_finite()) {
+                point
+            } else {
+                [0.0; 3]
+            }
+        })
+        .collect();
+    let colors = if let Some((r, g, b)) = rgb {
+        Some(
+            records
+                .iter()
+                .map(|v| [v[r] as u8, v[g] as u8, v[b] as u8])
+                .collect(),
+        )
+    } else {
+        packed.map(|c| {
+            records
+                .iter()
+                .map(|v| {
+                    let [_, r, g, b] = (v[c] as u32).to_be_bytes();
+                    [r, g, b]
+                })
+                .collect()
+        })
+    };
+    let intensities = intensity.map(|i| {
+        records
+            .iter()
+            .map(|v| v[i].round().clamp(0.0, u16::MAX as f64) as u16)
+            .collect()
+    });
+    Ok(PointCloud {
+        width,
+        height,
+        points,
+        colors,
+        intensities,
+    })
+}
+
+/// The width of an unorganized point cloud of `count` points.
+fn unorganized_width(count: usize) -> io::Result<u32> {
+    u32::try_from(count).map_err(|_| invalid_data("too many points"))
+}
+
+/// Writes point `i` as one line of x, y, z, the color, and the intensity, as used by XYZ and ASCII PLY files.
+fn write_text_point(writer: &mut impl Write, cloud: &PointCloud, i: usize) -> io::Result<()> {
+    let [x, y, z] = cloud.points[i];
+    write!(writer, "{} {} {}", x, y, z)?;
+    if let Some(colors) = &cloud.colors {
+        let [r, g, b] = colors[i];
+        write!(writer, " {} {} {}", r, g, b)?;
+    }
+    if let Some(intensities) = &cloud.intensities {
+        write!(writer, " {}", intensities[i])?;
+    }
+    writeln!(writer)
+}
+
+/// The indices of the points with depth.
+fn valid_indices(cloud: &PointCloud) -> Vec<usize> {
+    (0..cloud.points.len())
+        .filter(|&i| cloud.points[i][2] > 0.0)
+        .collect()
+}
+
+fn check_lengths(cloud: &PointCloud) -> io::Result<()> {
+    let count = cloud.points.len();
+    if cloud.colors.as_ref().is_some_and(|c| c.len() != count)
+        || cloud.intensities.as_ref().is_some_and(|i| i.len() != count)
+    {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            "number of colors or intensities does not match the number of points",
+        ));
+    }
+    Ok(())
+}
+
+/// Reads one line of a header, without the line break.
+fn read_header_line(reader: &mut impl BufRead) -> io::Result<String> {
+    let mut line = Vec::new();
+    if reader.read_until(b'\n', &mut line)? == 0 {
+        return Err(invalid_data("file ends within the header"));
+    }
+    Ok(String::from_utf8_lossy(&line).trim().to_string())
+}
+
+fn parse<T: std::str::FromStr>(token: &str) -> io::Result<T> {
+    token
+        .parse()
+        .map_err(|_| invalid_data("cannot parse a number"))
+}
+
+fn invalid_data(message: &'static str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Resolution;
+
+    /// An organized 2x2 cloud with one pixel without depth.
+    fn cloud(colors: bool, intensities: bool) -> PointCloud {
+        PointCloud {
+            width: 2,
+            height: 2,
+            points: vec![
+                [-1.5, 2.25, 500.0],
+                [0.0, 0.0, 0.0],
+                [3.0, -0.125, 1234.5],
+                [10.0, 20.0, 4000.0],
+            ],
+            colors: colors.then(|| vec![[255, 0, 10], [1, 2, 3], [0, 128, 64], [7, 8, 9]]),
+            intensities: intensities.then(|| vec![0, 5, 1000, u16::MAX]),
+        }
+    }
+
+    /// The valid points of `cloud` as read back from a file.
+    fn unorganized(cloud: &PointCloud) -> PointCloud {
+        let indices = valid_indices(cloud);
+        PointCloud {
+            width: indices.len() as u32,
+            height: 1,
+            points: indices.iter().map(|&i| cloud.points[i]).collect(),
+            colors: cloud
+                .colors
+                .as_ref()
+                .map(|c| indices.iter().map(|&i| c[i]).collect()),
+            intensities: cloud
+                .intensities
+                .as_ref()
+                .map(|v| indices.iter().map(|&i| v[i]).collect()),
+        }
+    }
+
+    /// Calls `round_trip` for every combination of colors and intensities.
+    fn for_all_clouds(round_trip: impl Fn(&PointCloud) -> io::Result<PointCloud>) {
+        for colors in [false, true] {
+            for intensities in [false, true] {
+                let cloud = cloud(colors, intensities);
+                assert_eq!(round_trip(&cloud).unwrap(), unorganized(&cloud));
+            }
+        }
+    }
+
+    #[test]
+    fn ply_round_trip() {
+        for encoding in [Encoding::Ascii, Encoding::Binary] {
+            for_all_clouds(|cloud| {
+                let mut bytes = Vec::new();
+                write_ply_to(&mut bytes, cloud, encoding)?;
+                read_ply_from(bytes.as_slice())
+            });
+        }
+    }
+
+    #[test]
+    fn pcd_round_trip() {
+        for encoding in [Encoding::Ascii, Encoding::Binary] {
+            for_all_clouds(|cloud| {
+                let mut bytes = Vec::new();
+                write_pcd_to(&mut bytes, cloud, encoding)?;
+                read_pcd_from(bytes.as_slice())
+            });
+        }
+    }
+
+    #[test]
+    fn xyz_round_trip() {
+        for_all_clouds(|cloud| {
+            let mut bytes = Vec::new();
+            write_xyz_to(&mut bytes, cloud)?;
+            read_xyz_from(bytes.as_slice())
+        });
+    }
+
+    #[test]
+    fn pcd_packed_float_rgb() {
+        let rgb = f32::from_bits(0x00ff8040);
+        let header = "FIELDS x y z rgb\nSIZE 4 4 4 4\nTYPE F F F F\nWIDTH 1\nHEIGHT 1\n";
+        let ascii = format!("{}DATA ascii\n1 2 3 {}\n", header, rgb);
+        let mut binary = format!("{}DATA binary\n", header).into_bytes();
+        for value in [1.0f32, 2.0, 3.0, rgb] {
+            binary.extend(value.to_le_bytes());
+        }
+        for bytes in [ascii.as_bytes(), binary.as_slice()] {
+            let cloud = read_pcd_from(bytes).unwrap();
+            assert_eq!(cloud.points, vec![[1.0, 2.0, 3.0]]);
+            assert_eq!(cloud.colors, Some(vec![[255, 128, 64]]));
+        }
+    }
+
+    #[test]
+    fn pcd_organized() {
+        let pcd = "FIELDS x y z\nSIZE 4 4 4\nTYPE F F F\nWIDTH 2\nHEIGHT 2\nPOINTS 4\nDATA ascii\n\
+            1 2 3\nnan nan nan\n4 5 6\n7 8 9\n";
+        let cloud = read_pcd_from(pcd.as_bytes()).unwrap();
+        assert_eq!(cloud.resolution(), Resolution::new(2, 2));
+        assert_eq!(cloud.points[1], [0.0; 3]);
+    }
+
+    #[test]
+    fn pcd_invalid_header_counts() {
+        let header = "FIELDS x y z\nSIZE 4 4 4\nTYPE F F F\n";
+        let overflow = format!("{}WIDTH 4294967295\nHEIGHT 2\nDATA ascii\n", header);
+        let too_many = format!("{}WIDTH 1\nPOINTS 1000000000000\nDATA binary\n", header);
+        for pcd in [overflow, too_many] {
+            let error = read_pcd_from(pcd.as_bytes()).unwrap_err();
+            assert_eq!(error.kind(), io::ErrorKind::InvalidData);
+        }
+    }
+
+    #[test]
+    fn pcd_inconsistent_fields() {
+        let header = "FIELDS x y z intensity\nSIZE 4 4 4 4\nTYPE F F F F\n";
+        let short_count = format!("{}COUNT 1 1 1\nWIDTH 1\nDATA ascii\n1 2 3 4\n", header);
+        let short_size = "FIELDS x y z\nSIZE 4 4\nTYPE F F F\nWIDTH 1\nDATA ascii\n1 2 3\n";
+        for pcd in [short_count.as_str(), short_size] {
+            let error = read_pcd_from(pcd.as_bytes()).unwrap_err();
+            assert_eq!(error.kind(), io::ErrorKind::InvalidData);
+        }
+    }
+
+    #[test]
+    fn xyz_empty() {
+        assert_eq!(read_xyz_from(&b""[..]).unwrap(), PointCloud::default());
+        assert_eq!(
+            read_xyz_from(&b"# no points\n\n"[..]).unwrap(),
+            PointCloud::default()
+        );
+    }
+}
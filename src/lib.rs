@@ -1,8 +1,22 @@
 #![doc = include_str!("../README.md")]
 // #![warn(missing_docs)]
 
+pub mod calibration;
+pub mod camera;
+pub mod clock;
 pub mod dcam560;
+mod device_info;
+mod error;
+pub mod filter;
+pub mod frame;
+pub mod io;
+pub mod point_cloud;
 pub mod scepter;
+pub mod stream;
+pub mod supervisor;
+
+pub use device_info::{ConnectStatus, DeviceInfo, HotPlugEvent};
+pub use error::Error;
 
 pub mod util;
 
@@ -13,13 +27,25 @@ pub const DEFAULT_RESOLUTION: Resolution = Resolution::new(640, 480);
 pub const DEFAULT_PIXEL_COUNT: usize = DEFAULT_RESOLUTION.to_pixel_count();
 
 /// Choose RGB or BGR format.
+#[derive(Clone, Copy, Debug, PartialEq)]
 pub enum ColorFormat {
     Rgb,
     Bgr,
 }
 
+/// Alignment of depth and color frames by the API.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Alignment {
+    /// Depth and color frames are provided in the space of their own sensor.
+    None,
+    /// The color frame is mapped to depth camera space at the depth resolution, see `map_color_to_depth()`.
+    ColorToDepth,
+    /// An additional depth frame mapped to color camera space is provided at the color resolution, see `aligned_depth_frame()`.
+    DepthToColor,
+}
+
 /// Possible color resolutions.
-#[derive(PartialEq)]
+#[derive(Clone, Copy, Debug, PartialEq)]
 pub enum ColorResolution {
     Res640x480,
     Res800x600,
@@ -27,7 +53,7 @@ pub enum ColorResolution {
 }
 
 /// Frame resolution.
-#[derive(PartialEq)]
+#[derive(Clone, Copy, Debug, PartialEq)]
 pub struct Resolution {
     width: u32,
     height: u32,
@@ -57,6 +83,7 @@ impl Resolution {
 }
 
 /// Possible depth measuring ranges. Only used for DCAM560.
+#[derive(Clone, Copy, Debug, PartialEq)]
 pub enum DepthMeasuringRange {
     Near,
     Mid,
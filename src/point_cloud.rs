@@ -0,0 +1,406 @@
+//! Point clouds computed from depth frames. The deprojection is done in pure Rust from the depth data and the `Intrinsics` of the depth sensor, so recorded depth frames can be converted without a device. `sdk_point_cloud()` of the respective API computes the point cloud of the current frame with the API, e.g. to cross-check the results. `Registration` colors point clouds by projecting them into the color image.
+
+use crate::{
+    Alignment, Error, Resolution,
+    calibration::{Extrinsics, Intrinsics},
+    camera::Camera,
+    frame::{FrameSet, PixelFormat},
+};
+
+/// Number of iterations to invert the distortion model, enough to converge for the lenses of the cameras.
+const UNDISTORT_ITERATIONS: usize = 10;
+
+/// A point cloud organized like the depth frame it was computed from: `points[y * width + x]` is the point of pixel (x, y) in mm in the coordinate system of the depth sensor. Pixels without depth are `[0.0; 3]`.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct PointCloud {
+    pub width: u32,
+    pub height: u32,
+    pub points: Vec<[f32; 3]>,
+    /// The color of each point, see `Registration`. Points which are not seen by the color sensor are black.
+    pub colors: Option<Vec<[u8; 3]>>,
+    /// The IR intensity of each point, see `set_intensities()`.
+    pub intensities: Option<Vec<u16>>,
+}
+impl PointCloud {
+    /// The resolution of the depth frame the point cloud was computed from.
+    pub const fn resolution(&self) -> Resolution {
+        Resolution::new(self.width, self.height)
+    }
+
+    /// The points of pixels with depth, i.e. z > 0.
+    pub fn valid_points(&self) -> impl Iterator<Item = &[f32; 3]> {
+        self.points.iter().filter(|p| p[2] > 0.0)
+    }
+
+    /// Adds the IR intensity of each pixel, e.g. the data of the IR frame of the same capture. Returns `Error::InvalidParameter` if the number of values does not match the number of points.
+    pub fn set_intensities(&mut self, intensities: &[u16]) -> Result<(), Error> {
+        if intensities.len() != self.points.len() {
+            return Err(Error::InvalidParameter(
+                "number of intensities does not match the number of points",
+            ));
+        }
+        self.intensities = Some(intensities.to_vec());
+        Ok(())
+    }
+
+    /// The largest distance in mm between corresponding points of both point clouds, e.g. to compare the pure Rust deprojection with `sdk_point_cloud()`. Only points which are valid in both clouds are compared. Returns `None` if the resolutions differ.
+    pub fn max_difference(&self, other: &PointCloud) -> Option<f32> {
+        if self.resolution() != other.resolution() || self.points.len() != other.points.len() {
+            return None;
+        }
+        Some(
+            self.points
+                .iter()
+                .zip(&other.points)
+                .filter(|(a, b)| a[2] > 0.0 && b[2] > 0.0)
+                .map(|(a, b)| {
+                    let d = [a[0] - b[0], a[1] - b[1], a[2] - b[2]];
+                    (d[0] * d[0] + d[1] * d[1] + d[2] * d[2]).sqrt()
+                })
+                .fold(0.0, f32::max),
+        )
+    }
+}
+
+/// Deprojects depth frames of a fixed resolution into point clouds. The viewing ray of each pixel is computed once, including the optional inversion of the lens distortion, so converting many frames is cheap.
+#[derive(Clone, Debug)]
+pub struct Deprojector {
+    resolution: Resolution,
+    /// x and y of the viewing ray of each pixel at z = 1.
+    rays: Vec<[f32; 2]>,
+}
+impl Deprojector {
+    /// Creates a deprojector for depth frames of `resolution` taken by a sensor with `intrinsics`. With `undistort`, the lens distortion described by the intrinsics is removed.
+    pub fn new(resolution: Resolution, intrinsics: &Intrinsics, undistort: bool) -> Self {
+        let (width, height) = resolution.to_tuple();
+        let mut rays = Vec::with_capacity(resolution.to_pixel_count());
+        for v in 0..height {
+            for u in 0..width {
+                let x = (u as f64 - intrinsics.cx) / intrinsics.fx;
+                let y = (v as f64 - intrinsics.cy) / intrinsics.fy;
+                let (x, y) = if undistort {
+                    undistort_point(x, y, intrinsics)
+                } else {
+                    (x, y)
+                };
+                rays.push([x as f32, y as f32]);
+            }
+        }
+        Self { resolution, rays }
+    }
+
+    /// The resolution of the depth frames this deprojector converts.
+    pub const fn resolution(&self) -> Resolution {
+        self.resolution
+    }
+
+    /// Converts depth in mm into a point cloud. Returns `Error::InvalidParameter` if `depth_mm` does not match the resolution of the deprojector.
+    pub fn deproject(&self, depth_mm: &[u16]) -> Result<PointCloud, Error> {
+        if depth_mm.len() != self.rays.len() {
+            return Err(Error::InvalidParameter(
+                "depth data does not match the resolution of the deprojector",
+            ));
+        }
+        let points = self
+            .rays
+            .iter()
+            .zip(depth_mm)
+            .map(|([x, y], depth)| {
+                let z = *depth as f32;
+                [x * z, y * z, z]
+            })
+            .collect();
+        let (width, height) = self.resolution.to_tuple();
+        Ok(PointCloud {
+            width,
+            height,
+            points,
+            colors: None,
+            intensities: None,
+        })
+    }
+}
+
+/// Registration of the depth and color sensor to compute colored point clouds. The points are deprojected from the depth frame and projected into the color image using the intrinsics of both sensors and the extrinsics between them, like `scTransformDepthPointToColorPoint` (Scepter) and `Ps2_GetMappedPointDepthToRGB` (DCAM560) do for single points. Unlike `map_color_to_depth()`, the color is sampled from the color frame at its full resolution, e.g. 1600x1200.
+#[derive(Clone, Debug)]
+pub struct Registration {
+    deprojector: Deprojector,
+    color_resolution: Resolution,
+    color_intrinsics: Intrinsics,
+    extrinsics: Extrinsics,
+    undistort: bool,
+}
+impl Registration {
+    /// Creates a registration for depth frames of `depth_resolution` and color frames of `color_resolution`. `color_intrinsics` have to refer to `color_resolution`, see `Intrinsics::scaled()`. With `undistort`, the lens distortion of both sensors is taken into account.
+    pub fn new(
+        depth_resolution: Resolution,
+        depth_intrinsics: &Intrinsics,
+        color_resolution: Resolution,
+        color_intrinsics: &Intrinsics,
+        extrinsics: &Extrinsics,
+        undistort: bool,
+    ) -> Self {
+        Self {
+            deprojector: Deprojector::new(depth_resolution, depth_intrinsics, undistort),
+            color_resolution,
+            color_intrinsics: *color_intrinsics,
+            extrinsics: *extrinsics,
+            undistort,
+        }
+    }
+
+    /// Creates a registration from the calibration reported by `camera`, for depth frames of its depth resolution and color frames of the current color resolution. The APIs do not state the resolution the color intrinsics refer to, so it has to be passed as `color_calibration_resolution`, the intrinsics are scaled from it to the current color resolution. Returns `Error::InvalidParameter` if color is mapped to depth, since the color frame then no longer matches the color intrinsics.
+    pub fn from_camera(
+        camera: &dyn Camera,
+        color_calibration_resolution: Resolution,
+        undistort: bool,
+    ) -> Result<Self, Error> {
+        if camera.get_alignment() == Alignment::ColorToDepth {
+            return Err(Error::InvalidParameter(
+                "color must not be mapped to depth for a registration",
+            ));
+        }
+        let color_resolution = camera.get_color_resolution()?;
+        Ok(Self::new(
+            camera.get_depth_resolution()?,
+            &camera.get_depth_intrinsics()?,
+            color_resolution,
+            &camera
+                .get_color_intrinsics()?
+                .scaled(color_calibration_resolution, color_resolution),
+            &camera.get_extrinsics()?,
+            undistort,
+        ))
+    }
+
+    /// Computes the point cloud of `depth_mm` and colors it from `color`, which holds three bytes per pixel. The bytes are copied in the order of the color frame. Returns `Error::InvalidParameter` if the data does not match the resolutions of the registration.
+    pub fn colored_point_cloud(&self, depth_mm: &[u16], color: &[u8]) -> Result<PointCloud, Error> {
+        let (width, height) = self.color_resolution.to_tuple();
+        if color.len() != 3 * self.color_resolution.to_pixel_count() {
+            return Err(Error::InvalidParameter(
+                "color data does not match the color resolution of the registration",
+            ));
+        }
+        let mut cloud = self.deprojector.deproject(depth_mm)?;
+        let i = &self.color_intrinsics;
+        let colors = cloud
+            .points
+            .iter()
+            .map(|point| {
+                if point[2] <= 0.0 {
+                    return [0; 3];
+                }
+                let [x, y, z] = self.extrinsics.transform(point.map(|c| c as f64));
+                if z <= 0.0 {
+                    return [0; 3];
+                }
+                let (x, y) = if self.undistort {
+                    distort_point(x / z, y / z, i)
+                } else {
+                    (x / z, y / z)
+                };
+                let u = (i.fx * x + i.cx).round();
+                let v = (i.fy * y + i.cy).round();
+                if u < 0.0 || v < 0.0 || u >= width as f64 || v >= height as f64 {
+                    return [0; 3];
+                }
+                let index = 3 * (v as usize * width as usize + u as usize);
+                [color[index], color[index + 1], color[index + 2]]
+            })
+            .collect();
+        cloud.colors = Some(colors);
+        Ok(cloud)
+    }
+
+    /// Computes the colored point cloud of the depth and color frame of `frame_set`, with colors in RGB order. Returns `None` if the set does not contain both frames.
+    pub fn frame_set_point_cloud(&self, frame_set: &FrameSet) -> Result<Option<PointCloud>, Error> {
+        let (Some(depth), Some(color)) = (&frame_set.depth, &frame_set.color) else {
+            return Ok(None);
+        };
+        if color.resolution() != self.color_resolution {
+            return Err(Error::InvalidParameter(
+                "color frame does not match the color resolution of the registration",
+            ));
+        }
+        let mut cloud = self.colored_point_cloud(&depth.data, &color.data)?;
+        if color.pixel_format == PixelFormat::Bgr888
+            && let Some(colors) = &mut cloud.colors
+        {
+            colors.iter_mut().for_each(|c| c.swap(0, 2));
+        }
+        Ok(Some(cloud))
+    }
+}
+
+/// Converts depth in mm of `resolution` into a point cloud, see `Deprojector` to convert several frames of the same sensor.
+pub fn deproject(
+    depth_mm: &[u16],
+    resolution: Resolution,
+    intrinsics: &Intrinsics,
+    undistort: bool,
+) -> Result<PointCloud, Error> {
+    Deprojector::new(resolution, intrinsics, undistort).deproject(depth_mm)
+}
+
+// private functions_______________________________________________________
+
+/// Inverts the distortion model of `Intrinsics` for the normalized image point (x, y) by fixed-point iteration.
+fn undistort_point(x_distorted: f64, y_distorted: f64, i: &Intrinsics) -> (f64, f64) {
+    let (mut x, mut y) = (x_distorted, y_distorted);
+    for _ in 0..UNDISTORT_ITERATIONS {
+        let r2 = x * x + y * y;
+        let r4 = r2 * r2;
+        let r6 = r4 * r2;
+        let radial =
+            (1.0 + i.k4 * r2 + i.k5 * r4 + i.k6 * r6) / (1.0 + i.k1 * r2 + i.k2 * r4 + i.k3 * r6);
+        let dx = 2.0 * i.p1 * x * y + i.p2 * (r2 + 2.0 * x * x);
+        let dy = i.p1 * (r2 + 2.0 * y * y) + 2.0 * i.p2 * x * y;
+        x = (x_distorted - dx) * radial;
+        y = (y_distorted - dy) * radial;
+    }
+    (x, y)
+}
+
+/// Applies the distortion model of `Intrinsics` to the normalized image point (x, y).
+fn distort_point(x: f64, y: f64, i: &Intrinsics) -> (f64, f64) {
+    let r2 = x * x + y * y;
+    let r4 = r2 * r2;
+    let r6 = r4 * r2;
+    let radial =
+        (1.0 + i.k1 * r2 + i.k2 * r4 + i.k3 * r6) / (1.0 + i.k4 * r2 + i.k5 * r4 + i.k6 * r6);
+    (
+        x * radial + 2.0 * i.p1 * x * y + i.p2 * (r2 + 2.0 * x * x),
+        y * radial + i.p1 * (r2 + 2.0 * y * y) + 2.0 * i.p2 * x * y,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::camera::mock::MockCamera;
+
+    const RESOLUTION: Resolution = Resolution::new(640, 480);
+
+    fn pinhole() -> Intrinsics {
+        Intrinsics {
+            fx: 500.0,
+            fy: 510.0,
+            cx: 320.0,
+            cy: 240.0,
+            ..Default::default()
+        }
+    }
+
+    /// Distortion coefficients in the range of the ToF and color lenses of the cameras.
+    fn distorted() -> Intrinsics {
+        Intrinsics {
+            k1: -0.28,
+            k2: 0.09,
+            k3: -0.01,
+            k4: 0.02,
+            k5: -0.005,
+            k6: 0.001,
+            p1: 0.0012,
+            p2: -0.0008,
+            ..pinhole()
+        }
+    }
+
+    fn depth_at(x: u32, y: u32, depth: u16) -> Vec<u16> {
+        let mut depth_mm = vec![0; RESOLUTION.to_pixel_count()];
+        depth_mm[(y * RESOLUTION.width + x) as usize] = depth;
+        depth_mm
+    }
+
+    #[test]
+    fn principal_point() {
+        let cloud = deproject(&depth_at(320, 240, 1000), RESOLUTION, &pinhole(), false).unwrap();
+        assert_eq!(cloud.resolution(), RESOLUTION);
+        assert_eq!(cloud.points[240 * 640 + 320], [0.0, 0.0, 1000.0]);
+        // the distortion vanishes at the principal point
+        let cloud = deproject(&depth_at(320, 240, 1000), RESOLUTION, &distorted(), true).unwrap();
+        assert_eq!(cloud.points[240 * 640 + 320], [0.0, 0.0, 1000.0]);
+    }
+
+    #[test]
+    fn known_pixel() {
+        let cloud = deproject(&depth_at(420, 140, 2000), RESOLUTION, &pinhole(), false).unwrap();
+        let [x, y, z] = cloud.points[140 * 640 + 420];
+        assert!((x - 400.0).abs() < 1e-3);
+        assert!((y - -2000.0 * 100.0 / 510.0).abs() < 1e-3);
+        assert_eq!(z, 2000.0);
+        assert_eq!(cloud.valid_points().count(), 1);
+    }
+
+    #[test]
+    fn undistort_inverts_distort() {
+        let intrinsics = distorted();
+        for (x, y) in [
+            (0.0, 0.0),
+            (0.1, -0.05),
+            (-0.4, 0.3),
+            (0.6, 0.45),
+            (-0.64, -0.47),
+        ] {
+            let (xd, yd) = distort_point(x, y, &intrinsics);
+            let (xu, yu) = undistort_point(xd, yd, &intrinsics);
+            assert!((xu - x).abs() < 1e-6 && (yu - y).abs() < 1e-6, "{x} {y}");
+        }
+    }
+
+    #[test]
+    fn wrong_depth_length() {
+        let deprojector = Deprojector::new(RESOLUTION, &pinhole(), false);
+        assert_eq!(deprojector.resolution(), RESOLUTION);
+        for length in [
+            0,
+            RESOLUTION.to_pixel_count() - 1,
+            RESOLUTION.to_pixel_count() + 1,
+        ] {
+            assert!(matches!(
+                deprojector.deproject(&vec![1000; length]),
+                Err(Error::InvalidParameter(_))
+            ));
+        }
+    }
+
+    #[test]
+    fn max_difference() {
+        let cloud = deproject(&depth_at(420, 140, 2000), RESOLUTION, &pinhole(), false).unwrap();
+        let other = deproject(&depth_at(420, 140, 2010), RESOLUTION, &pinhole(), false).unwrap();
+        let difference = cloud.max_difference(&other).unwrap();
+        assert!((difference - 10.0 * (1.0f32 + 0.2 * 0.2 + 0.196 * 0.196).sqrt()).abs() < 0.01);
+
+        let small = Resolution::new(320, 240);
+        let other = deproject(
+            &vec![2000; small.to_pixel_count()],
+            small,
+            &pinhole(),
+            false,
+        );
+        assert_eq!(cloud.max_difference(&other.unwrap()), None);
+    }
+
+    #[test]
+    fn set_intensities() {
+        let mut cloud = deproject(&depth_at(0, 0, 500), RESOLUTION, &pinhole(), false).unwrap();
+        assert!(cloud.set_intensities(&[1, 2, 3]).is_err());
+        assert!(
+            cloud
+                .set_intensities(&vec![7; RESOLUTION.to_pixel_count()])
+                .is_ok()
+        );
+        assert_eq!(cloud.intensities.unwrap()[0], 7);
+    }
+
+    #[test]
+    fn registration_from_camera() {
+        let mut camera = MockCamera::default();
+        assert!(Registration::from_camera(&camera, RESOLUTION, false).is_ok());
+        camera.map_color_to_depth(true);
+        assert!(matches!(
+            Registration::from_camera(&camera, RESOLUTION, false),
+            Err(Error::InvalidParameter(_))
+        ));
+    }
+}
@@ -0,0 +1,81 @@
+//! Calibration of the ToF and color sensors, see `calibration`.
+
+use sys::ScStatus_SC_OK as OK;
+use vzense_sys::scepter as sys;
+
+use crate::{
+    Error, Resolution,
+    calibration::{Extrinsics, Intrinsics},
+};
+
+use super::device::Device;
+
+impl Device {
+    /// Intrinsic parameters of the ToF sensor.
+    pub fn get_depth_intrinsics(&self) -> Result<Intrinsics, Error> {
+        self.get_intrinsics(sys::ScSensorType_SC_TOF_SENSOR)
+    }
+
+    /// Intrinsic parameters of the color sensor.
+    pub fn get_color_intrinsics(&self) -> Result<Intrinsics, Error> {
+        self.get_intrinsics(sys::ScSensorType_SC_COLOR_SENSOR)
+    }
+
+    /// Resolution of the ToF sensor, the first one in its list of supported resolutions.
+    pub fn get_depth_resolution(&self) -> Result<Resolution, Error> {
+        let mut list = sys::ScResolutionList::default();
+        let status = unsafe {
+            sys::scGetSupportedResolutionList(
+                self.handle,
+                sys::ScSensorType_SC_TOF_SENSOR,
+                &mut list,
+            )
+        };
+        if status != OK {
+            return Err(Error::scepter("get supported resolution list", status));
+        }
+        if list.count < 1 {
+            return Err(Error::Unsupported("querying the ToF resolution"));
+        }
+        let resolution = list.resolution[0];
+        Ok(Resolution::new(
+            resolution.width as u32,
+            resolution.height as u32,
+        ))
+    }
+
+    /// Extrinsic parameters from the ToF sensor to the color sensor.
+    pub fn get_extrinsics(&self) -> Result<Extrinsics, Error> {
+        let mut params = sys::ScSensorExtrinsicParameters::default();
+        let status = unsafe { sys::scGetSensorExtrinsicParameters(self.handle, &mut params) };
+        if status != OK {
+            return Err(Error::scepter("get sensor extrinsic parameters", status));
+        }
+        Ok(Extrinsics::from_arrays(params.rotation, params.translation))
+    }
+
+    // private functions_______________________________________________________
+
+    fn get_intrinsics(&self, sensor: sys::ScSensorType) -> Result<Intrinsics, Error> {
+        let mut params = sys::ScSensorIntrinsicParameters::default();
+        let status =
+            unsafe { sys::scGetSensorIntrinsicParameters(self.handle, sensor, &mut params) };
+        if status != OK {
+            return Err(Error::scepter("get sensor intrinsic parameters", status));
+        }
+        Ok(Intrinsics {
+            fx: params.fx,
+            fy: params.fy,
+            cx: params.cx,
+            cy: params.cy,
+            k1: params.k1,
+            k2: params.k2,
+            k3: params.k3,
+            k4: params.k4,
+            k5: params.k5,
+            k6: params.k6,
+            p1: params.p1,
+            p2: params.p2,
+        })
+    }
+}
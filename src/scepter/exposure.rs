@@ -0,0 +1,150 @@
+//! Exposure and gain control of the ToF and color sensors.
+
+use sys::ScStatus_SC_OK as OK;
+use vzense_sys::scepter as sys;
+
+use crate::Error;
+
+use super::device::Device;
+
+/// The image sensors of the camera.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Sensor {
+    /// The ToF sensor providing depth and IR frames.
+    Tof,
+    /// The color sensor.
+    Color,
+}
+impl Sensor {
+    const fn to_sys(self) -> sys::ScSensorType {
+        match self {
+            Sensor::Tof => sys::ScSensorType_SC_TOF_SENSOR,
+            Sensor::Color => sys::ScSensorType_SC_COLOR_SENSOR,
+        }
+    }
+}
+
+/// Exposure control modes of a sensor.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ExposureMode {
+    /// The exposure time is chosen by the camera.
+    Auto,
+    /// The exposure time is set with `set_exposure_time()`.
+    Manual,
+}
+
+impl Device {
+    /// Sets the exposure control mode of `sensor`. For the ToF sensor, HDR mode has to be disabled before switching to `ExposureMode::Auto`.
+    pub fn set_exposure_mode(&self, sensor: Sensor, mode: ExposureMode) -> Result<(), Error> {
+        let mode = match mode {
+            ExposureMode::Auto => sys::ScExposureControlMode_SC_EXPOSURE_CONTROL_MODE_AUTO,
+            ExposureMode::Manual => sys::ScExposureControlMode_SC_EXPOSURE_CONTROL_MODE_MANUAL,
+        };
+        let status = unsafe { sys::scSetExposureControlMode(self.handle, sensor.to_sys(), mode) };
+        if status != OK {
+            return Err(Error::scepter("set exposure control mode", status));
+        }
+        Ok(())
+    }
+
+    /// Returns the exposure control mode of `sensor`.
+    pub fn get_exposure_mode(&self, sensor: Sensor) -> Result<ExposureMode, Error> {
+        let mut mode = 0;
+        let status =
+            unsafe { sys::scGetExposureControlMode(self.handle, sensor.to_sys(), &mut mode) };
+        if status != OK {
+            return Err(Error::scepter("get exposure control mode", status));
+        }
+        Ok(match mode {
+            sys::ScExposureControlMode_SC_EXPOSURE_CONTROL_MODE_AUTO => ExposureMode::Auto,
+            _ => ExposureMode::Manual,
+        })
+    }
+
+    /// Sets the exposure time of `sensor` in µs. The sensor has to be in `ExposureMode::Manual`. Returns `Error::InvalidParameter` if the time exceeds `get_max_exposure_time()`.
+    pub fn set_exposure_time(&self, sensor: Sensor, exposure_time_us: u32) -> Result<(), Error> {
+        if exposure_time_us > self.get_max_exposure_time(sensor)? {
+            return Err(Error::InvalidParameter(
+                "exposure time exceeds the maximum exposure time",
+            ));
+        }
+        let status = unsafe {
+            sys::scSetExposureTime(self.handle, sensor.to_sys(), exposure_time_us as i32)
+        };
+        if status != OK {
+            return Err(Error::scepter("set exposure time", status));
+        }
+        Ok(())
+    }
+
+    /// Returns the exposure time of `sensor` in µs.
+    pub fn get_exposure_time(&self, sensor: Sensor) -> Result<u32, Error> {
+        let mut exposure_time = 0;
+        let status =
+            unsafe { sys::scGetExposureTime(self.handle, sensor.to_sys(), &mut exposure_time) };
+        if status != OK {
+            return Err(Error::scepter("get exposure time", status));
+        }
+        Ok(exposure_time as u32)
+    }
+
+    /// Returns the maximum exposure time of `sensor` in µs, which depends on the current frame rate.
+    pub fn get_max_exposure_time(&self, sensor: Sensor) -> Result<u32, Error> {
+        let mut exposure_time = 0;
+        let status =
+            unsafe { sys::scGetMaxExposureTime(self.handle, sensor.to_sys(), &mut exposure_time) };
+        if status != OK {
+            return Err(Error::scepter("get max exposure time", status));
+        }
+        Ok(exposure_time as u32)
+    }
+
+    /// Sets the maximum exposure time in µs the color sensor may choose in `ExposureMode::Auto`. Returns `Error::InvalidParameter` if the time exceeds `get_max_exposure_time(Sensor::Color)`.
+    pub fn set_color_auto_max_exposure_time(&self, exposure_time_us: u32) -> Result<(), Error> {
+        if exposure_time_us > self.get_max_exposure_time(Sensor::Color)? {
+            return Err(Error::InvalidParameter(
+                "exposure time exceeds the maximum exposure time",
+            ));
+        }
+        let status =
+            unsafe { sys::scSetColorAECMaxExposureTime(self.handle, exposure_time_us as i32) };
+        if status != OK {
+            return Err(Error::scepter("set color AEC max exposure time", status));
+        }
+        Ok(())
+    }
+
+    /// Returns the maximum exposure time in µs the color sensor may choose in `ExposureMode::Auto`.
+    pub fn get_color_auto_max_exposure_time(&self) -> Result<u32, Error> {
+        let mut exposure_time = 0;
+        let status = unsafe { sys::scGetColorAECMaxExposureTime(self.handle, &mut exposure_time) };
+        if status != OK {
+            return Err(Error::scepter("get color AEC max exposure time", status));
+        }
+        Ok(exposure_time as u32)
+    }
+
+    /// Sets the gain of the color sensor, used in `ExposureMode::Manual`. Different devices have different maximum values. Please refer to the device specification.
+    pub fn set_color_gain(&self, gain: f32) -> Result<(), Error> {
+        if !gain.is_finite() || gain < 0.0 {
+            return Err(Error::InvalidParameter(
+                "gain must be a non-negative number",
+            ));
+        }
+        let status = unsafe { sys::scSetColorGain(self.handle, gain) };
+        if status != OK {
+            return Err(Error::scepter("set color gain", status));
+        }
+        Ok(())
+    }
+
+    /// Returns the gain of the color sensor.
+    pub fn get_color_gain(&self) -> Result<f32, Error> {
+        let mut gain = 0.0;
+        let status = unsafe { sys::scGetColorGain(self.handle, &mut gain) };
+        if status != OK {
+            return Err(Error::scepter("get color gain", status));
+        }
+        Ok(gain)
+    }
+}
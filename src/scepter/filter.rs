@@ -0,0 +1,132 @@
+//! Depth filters running on the camera, configured with `DepthFilterConfig`.
+
+use sys::ScStatus_SC_OK as OK;
+use vzense_sys::scepter as sys;
+
+use crate::{Error, camera::Backend, filter::DepthFilterConfig};
+
+use super::device::Device;
+
+impl Device {
+    /// Applies all settings of `config` which are not `None`. The configuration is validated before anything is changed on the device.
+    pub fn set_depth_filters(&self, config: &DepthFilterConfig) -> Result<(), Error> {
+        config.validate(Backend::Scepter)?;
+
+        if config.time_filter.is_some() || config.time_filter_threshold.is_some() {
+            let mut params = self.get_time_filter_params()?;
+            if let Some(enable) = config.time_filter {
+                params.enable = enable;
+            }
+            if let Some(threshold) = config.time_filter_threshold {
+                params.threshold = threshold as i32;
+            }
+            let status = unsafe { sys::scSetTimeFilterParams(self.handle, params) };
+            if status != OK {
+                return Err(Error::scepter("set time filter params", status));
+            }
+        }
+
+        if config.confidence_filter.is_some() || config.confidence_threshold.is_some() {
+            let mut params = self.get_confidence_filter_params()?;
+            if let Some(enable) = config.confidence_filter {
+                params.enable = enable;
+            }
+            if let Some(threshold) = config.confidence_threshold {
+                params.threshold = threshold as i32;
+            }
+            let status = unsafe { sys::scSetConfidenceFilterParams(self.handle, params) };
+            if status != OK {
+                return Err(Error::scepter("set confidence filter params", status));
+            }
+        }
+
+        if config.flying_pixel_filter.is_some() || config.flying_pixel_threshold.is_some() {
+            let mut params = self.get_flying_pixel_filter_params()?;
+            if let Some(enable) = config.flying_pixel_filter {
+                params.enable = enable;
+            }
+            if let Some(threshold) = config.flying_pixel_threshold {
+                params.threshold = threshold as i32;
+            }
+            let status = unsafe { sys::scSetFlyingPixelFilterParams(self.handle, params) };
+            if status != OK {
+                return Err(Error::scepter("set flying pixel filter params", status));
+            }
+        }
+
+        if let Some(enable) = config.spatial_filter {
+            let status = unsafe { sys::scSetSpatialFilterEnabled(self.handle, enable as u8) };
+            if status != OK {
+                return Err(Error::scepter("set spatial filter enabled", status));
+            }
+        }
+
+        if let Some(enable) = config.fill_hole_filter {
+            let status = unsafe { sys::scSetFillHoleFilterEnabled(self.handle, enable as u8) };
+            if status != OK {
+                return Err(Error::scepter("set fill hole filter enabled", status));
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Reads back the current configuration of all depth filters.
+    pub fn get_depth_filters(&self) -> Result<DepthFilterConfig, Error> {
+        let time = self.get_time_filter_params()?;
+        let confidence = self.get_confidence_filter_params()?;
+        let flying_pixel = self.get_flying_pixel_filter_params()?;
+
+        let mut spatial = 0;
+        let status = unsafe { sys::scGetSpatialFilterEnabled(self.handle, &mut spatial) };
+        if status != OK {
+            return Err(Error::scepter("get spatial filter enabled", status));
+        }
+
+        let mut fill_hole = 0;
+        let status = unsafe { sys::scGetFillHoleFilterEnabled(self.handle, &mut fill_hole) };
+        if status != OK {
+            return Err(Error::scepter("get fill hole filter enabled", status));
+        }
+
+        Ok(DepthFilterConfig {
+            time_filter: Some(time.enable),
+            time_filter_threshold: Some(time.threshold as u8),
+            confidence_filter: Some(confidence.enable),
+            confidence_threshold: Some(confidence.threshold as u16),
+            flying_pixel_filter: Some(flying_pixel.enable),
+            flying_pixel_threshold: Some(flying_pixel.threshold as u8),
+            spatial_filter: Some(spatial != 0),
+            fill_hole_filter: Some(fill_hole != 0),
+        })
+    }
+
+    // private functions_______________________________________________________
+
+    fn get_time_filter_params(&self) -> Result<sys::ScTimeFilterParams, Error> {
+        let mut params = sys::ScTimeFilterParams::default();
+        let status = unsafe { sys::scGetTimeFilterParams(self.handle, &mut params) };
+        if status != OK {
+            return Err(Error::scepter("get time filter params", status));
+        }
+        Ok(params)
+    }
+
+    fn get_confidence_filter_params(&self) -> Result<sys::ScConfidenceFilterParams, Error> {
+        let mut params = sys::ScConfidenceFilterParams::default();
+        let status = unsafe { sys::scGetConfidenceFilterParams(self.handle, &mut params) };
+        if status != OK {
+            return Err(Error::scepter("get confidence filter params", status));
+        }
+        Ok(params)
+    }
+
+    fn get_flying_pixel_filter_params(&self) -> Result<sys::ScFlyingPixelFilterParams, Error> {
+        let mut params = sys::ScFlyingPixelFilterParams::default();
+        let status = unsafe { sys::scGetFlyingPixelFilterParams(self.handle, &mut params) };
+        if status != OK {
+            return Err(Error::scepter("get flying pixel filter params", status));
+        }
+        Ok(params)
+    }
+}
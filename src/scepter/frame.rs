@@ -1,35 +1,33 @@
 //! Reading frames, retrieving data.
 
 use crate::{
-    red,
+    Alignment, Error,
+    frame::{
+        ColorFrame, DepthFrame, Frame, FrameMetadata, FrameSet, FrameView, IrFrame, PixelFormat,
+    },
+    point_cloud::PointCloud,
     util::{new_fixed_vec, normalize_u16_to_u8},
 };
 
-use super::{device::Device, get_message};
-use std::iter::zip;
+use super::device::Device;
+use std::{iter::zip, time::Instant};
 use sys::ScStatus_SC_OK as OK;
 use vzense_sys::scepter as sys;
 
-/// Captures the next image frame from `device`. This function must be called before getting frame data using `get_..._frame()`. `max_wait_time_ms` is the maximum waiting time for the next frame in milliseconds. The recommended value is 2000 / fps.
-pub fn read_next_frame(device: &mut Device, max_wait_time_ms: u16) -> i32 {
-    unsafe {
-        let status = sys::scGetFrameReady(device.handle, max_wait_time_ms, &mut device.frame_ready);
-        if status != OK {
-            println!(
-                "{}",
-                red!(
-                    "vzense_rust: read_next_frame failed with status {}",
-                    get_message(status)
-                )
-            );
-            return status;
-        }
+/// Captures the next image frame from `device`. This function must be called before getting frame data using `get_..._frame()`. `max_wait_time_ms` is the maximum waiting time for the next frame in milliseconds. The recommended value is 2000 / fps. Returns `Error::Timeout` if no frame was ready in time and `Error::Disconnected` if the connection to the device was lost.
+pub fn read_next_frame(device: &mut Device, max_wait_time_ms: u16) -> Result<(), Error> {
+    let status =
+        unsafe { sys::scGetFrameReady(device.handle, max_wait_time_ms, &mut device.frame_ready) };
+    if status != OK {
+        device.frame_ready = sys::ScFrameReady::default();
+        return Err(Error::scepter("read next frame", status));
     }
-    0
+    device.capture_time = Instant::now();
+    Ok(())
 }
 
-/// Raw depth data in mm as `u16`.
-pub fn get_depth_mm_u16_frame(device: &mut Device, depth_mm: &mut [u16]) {
+/// Raw depth data in mm as `u16`. Returns `false` if no depth frame was ready in the current capture.
+pub fn get_depth_mm_u16_frame(device: &mut Device, depth_mm: &mut [u16]) -> Result<bool, Error> {
     if device.frame_ready.depth() == 1 {
         let status = unsafe {
             sys::scGetFrame(
@@ -38,15 +36,20 @@ pub fn get_depth_mm_u16_frame(device: &mut Device, depth_mm: &mut [u16]) {
                 &mut device.frame,
             )
         };
-        check_frame(device, status);
+        check_frame(device, status)?;
         get_u16_data(device, depth_mm);
 
         device.current_frame_is_depth = true;
+        return Ok(true);
     }
+    Ok(false)
 }
 
-/// Depth data scaled according to `device.min_depth_mm` = 0 and `device.max_depth_mm` = 255 stored in a `u8` array.
-pub fn get_depth_scaled_u8_frame(device: &mut Device, depth_scaled: &mut [u8]) {
+/// Depth data scaled according to `device.min_depth_mm` = 0 and `device.max_depth_mm` = 255 stored in a `u8` array. Returns `false` if no depth frame was ready in the current capture.
+pub fn get_depth_scaled_u8_frame(
+    device: &mut Device,
+    depth_scaled: &mut [u8],
+) -> Result<bool, Error> {
     if device.frame_ready.depth() == 1 {
         let status = unsafe {
             sys::scGetFrame(
@@ -55,7 +58,7 @@ pub fn get_depth_scaled_u8_frame(device: &mut Device, depth_scaled: &mut [u8]) {
                 &mut device.frame,
             )
         };
-        check_frame(device, status);
+        check_frame(device, status)?;
         let mut depth_mm = new_fixed_vec(depth_scaled.len(), 0);
 
         get_u16_data(device, &mut depth_mm);
@@ -67,11 +70,13 @@ pub fn get_depth_scaled_u8_frame(device: &mut Device, depth_scaled: &mut [u8]) {
             depth_scaled,
         );
         device.current_frame_is_depth = true;
+        return Ok(true);
     }
+    Ok(false)
 }
 
-/// Raw IR data as `u8`.
-pub fn get_ir_frame(device: &mut Device, ir: &mut [u8]) {
+/// Raw IR data as `u8`. Returns `false` if no IR frame was ready in the current capture.
+pub fn get_ir_frame(device: &mut Device, ir: &mut [u8]) -> Result<bool, Error> {
     if device.frame_ready.ir() == 1 {
         let status = unsafe {
             sys::scGetFrame(
@@ -80,25 +85,239 @@ pub fn get_ir_frame(device: &mut Device, ir: &mut [u8]) {
                 &mut device.frame,
             )
         };
-        check_frame(device, status);
+        check_frame(device, status)?;
         get_u8_data(device, ir);
         device.current_frame_is_depth = false;
+        return Ok(true);
     }
+    Ok(false)
 }
 
-/// Color data as 24 bit stored in consecutive `u8`.
-pub fn get_color_frame(device: &mut Device, color: &mut [u8]) {
-    let frame_type = if device.color_is_mapped && device.frame_ready.transformedColor() == 1 {
-        sys::ScFrameType_SC_TRANSFORM_COLOR_IMG_TO_DEPTH_SENSOR_FRAME
-    } else if !device.color_is_mapped && device.frame_ready.color() == 1 {
-        sys::ScFrameType_SC_COLOR_FRAME
-    } else {
-        return;
+/// Color data as 24 bit stored in consecutive `u8`. Returns `false` if no color frame was ready in the current capture, and `Error::Unsupported` if the device sends JPEG compressed color frames (use `color_frame()` to get the compressed data).
+pub fn get_color_frame(device: &mut Device, color: &mut [u8]) -> Result<bool, Error> {
+    let Some(frame_type) = color_frame_type(device) else {
+        return Ok(false);
     };
     let status = unsafe { sys::scGetFrame(device.handle, frame_type, &mut device.frame) };
-    check_frame(device, status);
+    check_frame(device, status)?;
+    if pixel_format(device.frame.pixelFormat) == PixelFormat::Jpeg {
+        return Err(Error::Unsupported("decoding of JPEG color frames"));
+    }
     get_u8_data(device, color);
     device.current_frame_is_depth = false;
+    Ok(true)
+}
+
+/// Depth frame in mm including resolution and metadata. Returns `None` if no depth frame was ready in the current capture.
+pub fn depth_frame(device: &mut Device) -> Result<Option<DepthFrame>, Error> {
+    if device.frame_ready.depth() != 1 {
+        return Ok(None);
+    }
+    get_frame(device, sys::ScFrameType_SC_DEPTH_FRAME)?;
+    let mut data = new_fixed_vec(device.frame.dataLen as usize / 2, 0);
+    get_u16_data(device, &mut data);
+    device.current_frame_is_depth = true;
+    Ok(Some(to_owned_frame(device, data)))
+}
+
+/// Depth frame in mm mapped to color camera space at the color resolution, including resolution and metadata. Requires `Alignment::DepthToColor`. Returns `None` if no such frame was ready in the current capture.
+pub fn aligned_depth_frame(device: &mut Device) -> Result<Option<DepthFrame>, Error> {
+    if device.alignment != Alignment::DepthToColor || device.frame_ready.transformedDepth() != 1 {
+        return Ok(None);
+    }
+    get_frame(
+        device,
+        sys::ScFrameType_SC_TRANSFORM_DEPTH_IMG_TO_COLOR_SENSOR_FRAME,
+    )?;
+    let mut data = new_fixed_vec(device.frame.dataLen as usize / 2, 0);
+    get_u16_data(device, &mut data);
+    device.current_frame_is_depth = false;
+    Ok(Some(to_owned_frame(device, data)))
+}
+
+/// IR frame including resolution and metadata. Returns `None` if no IR frame was ready in the current capture.
+pub fn ir_frame(device: &mut Device) -> Result<Option<IrFrame>, Error> {
+    if device.frame_ready.ir() != 1 {
+        return Ok(None);
+    }
+    get_frame(device, sys::ScFrameType_SC_IR_FRAME)?;
+    let data = frame_data(device).iter().map(|v| *v as u16).collect();
+    device.current_frame_is_depth = false;
+    Ok(Some(to_owned_frame(device, data)))
+}
+
+/// Color frame including resolution and metadata. If color is mapped to depth, the mapped frame is returned. Returns `None` if no color frame was ready in the current capture.
+pub fn color_frame(device: &mut Device) -> Result<Option<ColorFrame>, Error> {
+    let Some(frame_type) = color_frame_type(device) else {
+        return Ok(None);
+    };
+    get_frame(device, frame_type)?;
+    let data = frame_data(device).to_vec();
+    device.current_frame_is_depth = false;
+    Ok(Some(to_owned_frame(device, data)))
+}
+
+/// All frames which were ready in the current capture. Unlike calling the single getters, the returned set makes explicit which frames belong to the same capture.
+pub fn frame_set(device: &mut Device) -> Result<FrameSet, Error> {
+    Ok(FrameSet {
+        depth: depth_frame(device)?,
+        ir: ir_frame(device)?,
+        color: color_frame(device)?,
+        host_time: device.capture_time,
+    })
+}
+
+/// Captures the next image frame and returns all frames which were ready in this capture, see `read_next_frame()` and `frame_set()`.
+pub fn read_frame_set(device: &mut Device, max_wait_time_ms: u16) -> Result<FrameSet, Error> {
+    read_next_frame(device, max_wait_time_ms)?;
+    frame_set(device)
+}
+
+/// Zero-copy view of the depth frame, valid until the next `read_next_frame()`. Unlike `depth_frame()`, the view does not update the frame used by `util::TouchDetector`. Returns `None` if no depth frame was ready in the current capture.
+pub fn depth_view(device: &Device) -> Result<Option<FrameView<'_>>, Error> {
+    if device.frame_ready.depth() != 1 {
+        return Ok(None);
+    }
+    get_view(device, sys::ScFrameType_SC_DEPTH_FRAME).map(Some)
+}
+
+/// Zero-copy view of the IR frame, valid until the next `read_next_frame()`. Returns `None` if no IR frame was ready in the current capture.
+pub fn ir_view(device: &Device) -> Result<Option<FrameView<'_>>, Error> {
+    if device.frame_ready.ir() != 1 {
+        return Ok(None);
+    }
+    get_view(device, sys::ScFrameType_SC_IR_FRAME).map(Some)
+}
+
+/// Zero-copy view of the color frame, valid until the next `read_next_frame()`. If color is mapped to depth, the mapped frame is returned. Returns `None` if no color frame was ready in the current capture.
+pub fn color_view(device: &Device) -> Result<Option<FrameView<'_>>, Error> {
+    let Some(frame_type) = color_frame_type(device) else {
+        return Ok(None);
+    };
+    get_view(device, frame_type).map(Some)
+}
+
+/// Point cloud of the depth frame of the current capture computed by the API, e.g. to cross-check `point_cloud::deproject()`. Returns `None` if no depth frame was ready in the current capture.
+pub fn sdk_point_cloud(device: &Device) -> Result<Option<PointCloud>, Error> {
+    if device.frame_ready.depth() != 1 {
+        return Ok(None);
+    }
+    let mut frame = sys::ScFrame::default();
+    let status =
+        unsafe { sys::scGetFrame(device.handle, sys::ScFrameType_SC_DEPTH_FRAME, &mut frame) };
+    if status != OK {
+        return Err(Error::scepter("get frame", status));
+    }
+    let (width, height) = (frame.width as u32, frame.height as u32);
+    let mut vectors = vec![sys::ScVector3f::default(); (width * height) as usize];
+    let status = unsafe {
+        sys::scConvertDepthFrameToPointCloudVector(device.handle, &frame, vectors.as_mut_ptr())
+    };
+    if status != OK {
+        return Err(Error::scepter(
+            "convert depth frame to point cloud vector",
+            status,
+        ));
+    }
+    Ok(Some(PointCloud {
+        width,
+        height,
+        points: vectors.iter().map(|v| [v.x, v.y, v.z]).collect(),
+        colors: None,
+        intensities: None,
+    }))
+}
+
+/// The color frame type to retrieve, depending on whether color is mapped to depth. `None` if no such frame is ready.
+fn color_frame_type(device: &Device) -> Option<sys::ScFrameType> {
+    if device.alignment == Alignment::ColorToDepth && device.frame_ready.transformedColor() == 1 {
+        Some(sys::ScFrameType_SC_TRANSFORM_COLOR_IMG_TO_DEPTH_SENSOR_FRAME)
+    } else if device.alignment != Alignment::ColorToDepth && device.frame_ready.color() == 1 {
+        Some(sys::ScFrameType_SC_COLOR_FRAME)
+    } else {
+        None
+    }
+}
+
+/// Retrieves the frame of type `frame_type` into `device.frame`.
+fn get_frame(device: &mut Device, frame_type: sys::ScFrameType) -> Result<(), Error> {
+    let status = unsafe { sys::scGetFrame(device.handle, frame_type, &mut device.frame) };
+    check_frame(device, status)
+}
+
+/// Retrieves the frame of type `frame_type` without touching `device.frame` and borrows its buffer.
+fn get_view(device: &Device, frame_type: sys::ScFrameType) -> Result<FrameView<'_>, Error> {
+    let mut frame = sys::ScFrame::default();
+    let status = unsafe { sys::scGetFrame(device.handle, frame_type, &mut frame) };
+    if status != OK {
+        return Err(Error::scepter("get frame", status));
+    }
+    if frame.pFrameData.is_null() {
+        return Err(Error::NullPointer("frame data"));
+    }
+    // the buffer stays valid until the next call to read the next frame, which needs `&mut Device`
+    let data = unsafe { std::slice::from_raw_parts(frame.pFrameData, frame.dataLen as usize) };
+    Ok(FrameView {
+        width: frame.width as u32,
+        height: frame.height as u32,
+        pixel_format: pixel_format(frame.pixelFormat),
+        frame_index: frame.frameIndex,
+        metadata: metadata(device, &frame),
+        data,
+    })
+}
+
+/// Wraps `data` together with the resolution and metadata of the current frame.
+fn to_owned_frame<T>(device: &Device, data: Vec<T>) -> Frame<T> {
+    let frame = device.frame;
+    Frame {
+        width: frame.width as u32,
+        height: frame.height as u32,
+        pixel_format: pixel_format(frame.pixelFormat),
+        frame_index: frame.frameIndex,
+        metadata: metadata(device, &frame),
+        data,
+    }
+}
+
+fn metadata(device: &Device, frame: &sys::ScFrame) -> FrameMetadata {
+    let is_tof_frame = matches!(
+        frame.frameType,
+        sys::ScFrameType_SC_DEPTH_FRAME
+            | sys::ScFrameType_SC_IR_FRAME
+            | sys::ScFrameType_SC_TRANSFORM_DEPTH_IMG_TO_COLOR_SENSOR_FRAME
+    );
+    FrameMetadata::Scepter {
+        device_timestamp: frame.deviceTimestamp,
+        sub_frame_count: if is_tof_frame {
+            device.sub_frame_count
+        } else {
+            1
+        },
+    }
+}
+
+fn pixel_format(format: sys::ScPixelFormat) -> PixelFormat {
+    match format {
+        sys::ScPixelFormat_SC_PIXEL_FORMAT_DEPTH_MM16 => PixelFormat::DepthMm16,
+        sys::ScPixelFormat_SC_PIXEL_FORMAT_GRAY_8 => PixelFormat::Gray8,
+        sys::ScPixelFormat_SC_PIXEL_FORMAT_RGB_888 => PixelFormat::Rgb888,
+        sys::ScPixelFormat_SC_PIXEL_FORMAT_BGR_888 => PixelFormat::Bgr888,
+        sys::ScPixelFormat_SC_PIXEL_FORMAT_RGB_888_JPEG
+        | sys::ScPixelFormat_SC_PIXEL_FORMAT_BGR_888_JPEG => PixelFormat::Jpeg,
+        sys::ScPixelFormat_SC_PIXEL_FORMAT_RGB_565 => PixelFormat::Rgb565,
+        sys::ScPixelFormat_SC_PIXEL_FORMAT_BGR_565 => PixelFormat::Bgr565,
+        other => PixelFormat::Unknown(other),
+    }
+}
+
+/// The data of the current frame as bytes.
+fn frame_data(device: &Device) -> &[u8] {
+    unsafe {
+        std::ptr::slice_from_raw_parts(device.frame.pFrameData, device.frame.dataLen as usize)
+            .as_ref()
+            .unwrap()
+    }
 }
 
 fn get_u16_data(device: &Device, data: &mut [u16]) {
@@ -127,14 +346,12 @@ fn get_u8_data(device: &Device, data: &mut [u8]) {
 }
 
 /// Check status of `scGetFrame()` and if data pointer is null.
-fn check_frame(device: &Device, status: sys::ScStatus) {
+fn check_frame(device: &Device, status: sys::ScStatus) -> Result<(), Error> {
     if status != OK {
-        panic!(
-            "{}",
-            red!("get_frame failed with status {}", get_message(status))
-        );
+        return Err(Error::scepter("get frame", status));
     }
     if device.frame.pFrameData.is_null() {
-        panic!("{}", red!("frame pointer is NULL!"));
+        return Err(Error::NullPointer("frame data"));
     }
+    Ok(())
 }
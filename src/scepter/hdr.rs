@@ -0,0 +1,262 @@
+//! HDR and WDR modes of the ToF sensor, which fuse several sub-frames with different exposure times into one depth frame. This allows to measure dark and reflective objects in the same scene at the cost of a lower output frame rate.
+
+use sys::ScStatus_SC_OK as OK;
+use vzense_sys::scepter as sys;
+
+use crate::Error;
+
+use super::device::Device;
+
+/// Multi-exposure modes of the ToF sensor. The exposure times are given in µs, one per sub-frame. If `exposure_times_us` is empty, the exposure times currently set on the device are kept.
+#[derive(Clone, Debug, PartialEq)]
+pub enum MultiExposure {
+    /// Each depth frame is captured with a single exposure.
+    Off,
+    /// High dynamic range mode. The ToF sensor has to be in `ExposureMode::Manual`, and HDR has to be disabled before switching to `ExposureMode::Auto`.
+    Hdr { exposure_times_us: Vec<u32> },
+    /// Wide dynamic range mode.
+    Wdr { exposure_times_us: Vec<u32> },
+}
+
+impl Device {
+    /// Applies the multi-exposure `config`. HDR and WDR exclude each other, so enabling one disables the other. Returns `Error::InvalidParameter` if the number of exposure times does not match the number of sub-frames of the mode, or if an exposure time exceeds the maximum for its sub-frame.
+    pub fn set_multi_exposure(&mut self, config: &MultiExposure) -> Result<(), Error> {
+        let (mode, exposure_times_us) = match config {
+            MultiExposure::Off => (None, &[][..]),
+            MultiExposure::Hdr { exposure_times_us } => (Some(Mode::Hdr), &exposure_times_us[..]),
+            MultiExposure::Wdr { exposure_times_us } => (Some(Mode::Wdr), &exposure_times_us[..]),
+        };
+        // the device is left unchanged if the exposure times are invalid
+        if let Some(mode) = mode {
+            validate_exposure_times(self, mode, exposure_times_us)?;
+        }
+        let result = apply_multi_exposure(self, mode, exposure_times_us);
+        // the mode might have changed even if a later step failed, the result of applying the config is returned either way
+        if let Ok(count) = self.query_sub_frame_count() {
+            self.sub_frame_count = count;
+        }
+        result
+    }
+
+    /// Returns the current multi-exposure mode including the exposure times of all sub-frames.
+    pub fn get_multi_exposure(&self) -> Result<MultiExposure, Error> {
+        for mode in [Mode::Hdr, Mode::Wdr] {
+            if is_enabled(self, mode)? {
+                let count = get_frame_count(self, mode)?;
+                let exposure_times_us = (0..count)
+                    .map(|i| get_exposure_time(self, mode, i))
+                    .collect::<Result<_, _>>()?;
+                return Ok(match mode {
+                    Mode::Hdr => MultiExposure::Hdr { exposure_times_us },
+                    Mode::Wdr => MultiExposure::Wdr { exposure_times_us },
+                });
+            }
+        }
+        Ok(MultiExposure::Off)
+    }
+
+    /// The number of sub-frames fused into one depth frame, 1 if neither HDR nor WDR is enabled. The output frame rate is the ToF frame rate divided by this number, so `max_wait_time_ms` of `read_next_frame()` should be scaled accordingly.
+    pub fn sub_frame_count(&self) -> u8 {
+        self.sub_frame_count
+    }
+
+    /// Returns the maximum exposure time in µs of sub-frame `index` of the HDR or WDR mode enabled in `config`.
+    pub fn get_max_sub_frame_exposure_time(
+        &self,
+        config: &MultiExposure,
+        index: u8,
+    ) -> Result<u32, Error> {
+        match config {
+            MultiExposure::Off => Err(Error::InvalidParameter(
+                "no sub-frames without HDR or WDR mode",
+            )),
+            MultiExposure::Hdr { .. } => get_max_exposure_time(self, Mode::Hdr, index),
+            MultiExposure::Wdr { .. } => get_max_exposure_time(self, Mode::Wdr, index),
+        }
+    }
+
+    /// Asks the device for the number of sub-frames of the enabled multi-exposure mode.
+    pub(super) fn query_sub_frame_count(&self) -> Result<u8, Error> {
+        for mode in [Mode::Hdr, Mode::Wdr] {
+            if is_enabled(self, mode)? {
+                return get_frame_count(self, mode);
+            }
+        }
+        Ok(1)
+    }
+}
+
+// private functions___________________________________________________________
+
+#[derive(Clone, Copy)]
+enum Mode {
+    Hdr,
+    Wdr,
+}
+
+/// Status codes for features the device or firmware does not have.
+fn is_unsupported(status: sys::ScStatus) -> bool {
+    status == -16 || status == -20
+}
+
+/// Enables `mode` (disabling the other one) or disables both if `mode` is `None`, and sets the exposure times.
+fn apply_multi_exposure(
+    device: &Device,
+    mode: Option<Mode>,
+    exposure_times_us: &[u32],
+) -> Result<(), Error> {
+    match mode {
+        None => {
+            set_enabled(device, Mode::Hdr, false)?;
+            set_enabled(device, Mode::Wdr, false)
+        }
+        Some(mode) => {
+            let other = match mode {
+                Mode::Hdr => Mode::Wdr,
+                Mode::Wdr => Mode::Hdr,
+            };
+            set_enabled(device, other, false)?;
+            set_enabled(device, mode, true)?;
+            set_exposure_times(device, mode, exposure_times_us)
+        }
+    }
+}
+
+fn set_enabled(device: &Device, mode: Mode, is_enabled: bool) -> Result<(), Error> {
+    let enabled = if is_enabled { 1 } else { 0 };
+    let (status, action) = match mode {
+        Mode::Hdr => (
+            unsafe { sys::scSetHDRModeEnabled(device.handle, enabled) },
+            "set HDR mode",
+        ),
+        Mode::Wdr => (
+            unsafe { sys::scSetWDRModeEnabled(device.handle, enabled) },
+            "set WDR mode",
+        ),
+    };
+    // disabling a mode the device does not have is not an error
+    if status != OK && (is_enabled || !is_unsupported(status)) {
+        return Err(Error::scepter(action, status));
+    }
+    Ok(())
+}
+
+fn is_enabled(device: &Device, mode: Mode) -> Result<bool, Error> {
+    let mut enabled = 0;
+    let (status, action) = match mode {
+        Mode::Hdr => (
+            unsafe { sys::scGetHDRModeEnabled(device.handle, &mut enabled) },
+            "get HDR mode",
+        ),
+        Mode::Wdr => (
+            unsafe { sys::scGetWDRModeEnabled(device.handle, &mut enabled) },
+            "get WDR mode",
+        ),
+    };
+    if status != OK {
+        if is_unsupported(status) {
+            return Ok(false);
+        }
+        return Err(Error::scepter(action, status));
+    }
+    Ok(enabled != 0)
+}
+
+fn get_frame_count(device: &Device, mode: Mode) -> Result<u8, Error> {
+    let mut count = 0;
+    let (status, action) = match mode {
+        Mode::Hdr => (
+            unsafe { sys::scGetFrameCountOfHDRMode(device.handle, &mut count) },
+            "get frame count of HDR mode",
+        ),
+        Mode::Wdr => (
+            unsafe { sys::scGetFrameCountOfWDRMode(device.handle, &mut count) },
+            "get frame count of WDR mode",
+        ),
+    };
+    if status != OK {
+        return Err(Error::scepter(action, status));
+    }
+    Ok(count.clamp(1, u8::MAX as i32) as u8)
+}
+
+fn get_exposure_time(device: &Device, mode: Mode, index: u8) -> Result<u32, Error> {
+    let mut exposure_time = 0;
+    let (status, action) = match mode {
+        Mode::Hdr => (
+            unsafe { sys::scGetExposureTimeOfHDR(device.handle, index, &mut exposure_time) },
+            "get exposure time of HDR",
+        ),
+        Mode::Wdr => (
+            unsafe { sys::scGetExposureTimeOfWDR(device.handle, index, &mut exposure_time) },
+            "get exposure time of WDR",
+        ),
+    };
+    if status != OK {
+        return Err(Error::scepter(action, status));
+    }
+    Ok(exposure_time as u32)
+}
+
+fn get_max_exposure_time(device: &Device, mode: Mode, index: u8) -> Result<u32, Error> {
+    let mut exposure_time = 0;
+    let (status, action) = match mode {
+        Mode::Hdr => (
+            unsafe { sys::scGetMaxExposureTimeOfHDR(device.handle, index, &mut exposure_time) },
+            "get max exposure time of HDR",
+        ),
+        Mode::Wdr => (
+            unsafe { sys::scGetMaxExposureTimeOfWDR(device.handle, index, &mut exposure_time) },
+            "get max exposure time of WDR",
+        ),
+    };
+    if status != OK {
+        return Err(Error::scepter(action, status));
+    }
+    Ok(exposure_time as u32)
+}
+
+/// Checks the number of exposure times and the maximum exposure time of each sub-frame. Empty exposure times are always valid.
+fn validate_exposure_times(
+    device: &Device,
+    mode: Mode,
+    exposure_times_us: &[u32],
+) -> Result<(), Error> {
+    if exposure_times_us.is_empty() {
+        return Ok(());
+    }
+    let count = get_frame_count(device, mode)?;
+    if exposure_times_us.len() != count as usize {
+        return Err(Error::InvalidParameter(
+            "number of exposure times does not match the number of sub-frames",
+        ));
+    }
+    for (index, exposure_time) in (0..count).zip(exposure_times_us) {
+        if *exposure_time > get_max_exposure_time(device, mode, index)? {
+            return Err(Error::InvalidParameter(
+                "exposure time exceeds the maximum exposure time of the sub-frame",
+            ));
+        }
+    }
+    Ok(())
+}
+
+/// Sets the exposure time of each sub-frame, see `validate_exposure_times()`.
+fn set_exposure_times(device: &Device, mode: Mode, exposure_times_us: &[u32]) -> Result<(), Error> {
+    for (index, exposure_time) in (0..).zip(exposure_times_us) {
+        let (status, action) = match mode {
+            Mode::Hdr => (
+                unsafe { sys::scSetExposureTimeOfHDR(device.handle, index, *exposure_time as i32) },
+                "set exposure time of HDR",
+            ),
+            Mode::Wdr => (
+                unsafe { sys::scSetExposureTimeOfWDR(device.handle, index, *exposure_time as i32) },
+                "set exposure time of WDR",
+            ),
+        };
+        if status != OK {
+            return Err(Error::scepter(action, status));
+        }
+    }
+    Ok(())
+}
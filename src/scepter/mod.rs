@@ -1,10 +1,17 @@
 //! The latest Scepter API for NYX650/660, DS86/87, DS77C, and DS77 cameras. See the [repository](https://github.com/ScepterSW/ScepterSDK) and the [API docs](https://github.com/ScepterSW/Scepter-Wiki/blob/master/en/ScepterSDK/BaseSDK.md).
 
+pub mod calibration;
 pub mod device;
+pub mod exposure;
+pub mod filter;
 pub mod frame;
+pub mod hdr;
+pub mod sdk;
+pub mod time_sync;
+pub mod trigger;
 
 /// Status messages from numeric code.
-const fn get_message(code: i32) -> &'static str {
+pub(crate) const fn get_message(code: i32) -> &'static str {
     match code {
         0 => "OK",                              // The function completed successfully.
         -1 => "DEVICE_IS_LIMBO",                // The device is limbo
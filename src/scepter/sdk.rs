@@ -0,0 +1,149 @@
+//! Reference-counted lifetime of the Scepter API, allowing several devices to be opened, streamed, and closed independently in one process.
+
+use std::{
+    os::raw::{c_int, c_void},
+    panic::{AssertUnwindSafe, catch_unwind},
+    sync::{
+        Arc, Mutex, PoisonError, Weak,
+        mpsc::{self, Receiver},
+    },
+    time::Duration,
+};
+
+use sys::ScStatus_SC_OK as OK;
+use vzense_sys::scepter as sys;
+
+use crate::{DeviceInfo, Error, HotPlugEvent, red};
+
+use super::device::to_device_info;
+
+/// The handle to the current `Sdk`, empty if the API is not initialized.
+static SDK: Mutex<Weak<Sdk>> = Mutex::new(Weak::new());
+
+/// Shared with `hot_plug_callback()`, so the callback can be called without holding `HOT_PLUG_CALLBACK`.
+type HotPlugCallback = Arc<Mutex<dyn FnMut(HotPlugEvent) + Send>>;
+
+/// The callback set by `Sdk::on_hot_plug()`, called from `hot_plug_callback()`.
+static HOT_PLUG_CALLBACK: Mutex<Option<HotPlugCallback>> = Mutex::new(None);
+
+/// The initialized Scepter API. Each `Device` holds an `Arc<Sdk>`, and the API is shut down when the last `Sdk` is dropped, so shutting down one device does not affect the others.
+#[derive(Debug)]
+pub struct Sdk {
+    _private: (),
+}
+impl Sdk {
+    /// Returns the shared `Sdk`, initializing the API if it is not initialized yet.
+    pub fn get(verbose: bool) -> Result<Arc<Self>, Error> {
+        let mut current = SDK.lock().unwrap_or_else(PoisonError::into_inner);
+        if let Some(sdk) = current.upgrade() {
+            return Ok(sdk);
+        }
+        initialize(verbose)?;
+        let sdk = Arc::new(Sdk { _private: () });
+        *current = Arc::downgrade(&sdk);
+        Ok(sdk)
+    }
+
+    /// Returns info about all devices found. Holding the `Sdk` while listing and opening devices keeps the found devices known to the API.
+    pub fn list_devices(&self, scan_time: Duration) -> Result<Vec<DeviceInfo>, Error> {
+        super::device::list_devices(scan_time)
+    }
+
+    /// Calls `callback` whenever a device is added or removed, replacing a previously set callback. The callback runs on a thread of the API. It stays active as long as the API is initialized.
+    pub fn on_hot_plug(
+        &self,
+        callback: impl FnMut(HotPlugEvent) + Send + 'static,
+    ) -> Result<(), Error> {
+        *HOT_PLUG_CALLBACK
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner) = Some(Arc::new(Mutex::new(callback)));
+        let status =
+            unsafe { sys::scSetHotPlugStatusCallback(Some(hot_plug_callback), std::ptr::null()) };
+        if status != OK {
+            *HOT_PLUG_CALLBACK
+                .lock()
+                .unwrap_or_else(PoisonError::into_inner) = None;
+            return Err(Error::scepter("set hot plug callback", status));
+        }
+        Ok(())
+    }
+
+    /// Returns a receiver for events of devices being added or removed, replacing a previously set callback (see `on_hot_plug()`).
+    pub fn hot_plug_events(&self) -> Result<Receiver<HotPlugEvent>, Error> {
+        let (sender, receiver) = mpsc::channel();
+        self.on_hot_plug(move |event| {
+            // the receiver might have been dropped, events are discarded then
+            let _ = sender.send(event);
+        })?;
+        Ok(receiver)
+    }
+}
+
+impl Drop for Sdk {
+    fn drop(&mut self) {
+        let current = SDK.lock().unwrap_or_else(PoisonError::into_inner);
+        // another `Sdk` might have been created while this one was dropped
+        if current.upgrade().is_some() {
+            return;
+        }
+        // no more events after shutting down
+        if HOT_PLUG_CALLBACK
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .take()
+            .is_some()
+        {
+            unsafe { sys::scSetHotPlugStatusCallback(None, std::ptr::null()) };
+        }
+        let status = unsafe { sys::scShutdown() };
+        if status != OK {
+            println!("{}", red!("{}", Error::scepter("shut down", status)));
+        }
+    }
+}
+
+/// Forwards the hot plug status of the API to the callback set by `Sdk::on_hot_plug()`. `state` is 0 if the device was added and 1 if it was removed.
+unsafe extern "C" fn hot_plug_callback(
+    info: *const sys::ScDeviceInfo,
+    state: c_int,
+    _user_data: *mut c_void,
+) {
+    if info.is_null() {
+        return;
+    }
+    let info = to_device_info(unsafe { &*info });
+    let event = if state == 0 {
+        HotPlugEvent::DeviceAdded(info)
+    } else {
+        HotPlugEvent::DeviceRemoved(info)
+    };
+    // the global lock is released before calling, so the callback can drop the last `Sdk` or set another callback
+    let callback = HOT_PLUG_CALLBACK
+        .lock()
+        .unwrap_or_else(PoisonError::into_inner)
+        .clone();
+    if let Some(callback) = callback {
+        let mut callback = callback.lock().unwrap_or_else(PoisonError::into_inner);
+        // unwinding into the API is not possible
+        if catch_unwind(AssertUnwindSafe(|| (*callback)(event))).is_err() {
+            println!("{}", red!("hot plug callback panicked"));
+        }
+    }
+}
+
+fn initialize(verbose: bool) -> Result<(), Error> {
+    if verbose {
+        println!("initializing...");
+    }
+    let status = unsafe { sys::scInitialize() };
+
+    // status -101 is reinitialization
+    if status == -101 {
+        if verbose {
+            println!("reinitializing...");
+        }
+    } else if status != OK {
+        return Err(Error::scepter("initialization", status));
+    }
+    Ok(())
+}
@@ -0,0 +1,64 @@
+//! Synchronization of the device clock by NTP or PTP. Once synchronized, the `device_timestamp` of the frames is given in ms since the Unix epoch and can be converted with `clock::to_system_time()`.
+
+use std::net::Ipv4Addr;
+
+use sys::ScStatus_SC_OK as OK;
+use vzense_sys::scepter as sys;
+
+use crate::{Error, device_info::string_from_c_chars};
+
+use super::device::Device;
+
+/// Time synchronization of the device clock.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum TimeSync {
+    /// The device clock runs freely, see `clock::DriftEstimator` to map its timestamps.
+    Off,
+    /// Synchronization with the NTP server at `server`.
+    Ntp { server: Ipv4Addr },
+    /// Synchronization by PTP with the master clock of the network.
+    Ptp,
+}
+
+impl Device {
+    /// Sets the time synchronization of the device clock.
+    pub fn set_time_sync(&self, time_sync: TimeSync) -> Result<(), Error> {
+        let mut config = sys::ScTimeSyncConfig::default();
+        match time_sync {
+            TimeSync::Off => config.flag = 0,
+            TimeSync::Ntp { server } => {
+                config.flag = 1;
+                // null-terminated dotted notation, which has at most 15 characters
+                let ip = server.to_string();
+                config.ip[..ip.len()].copy_from_slice(ip.as_bytes());
+            }
+            TimeSync::Ptp => config.flag = 2,
+        }
+        let status = unsafe { sys::scSetRealTimeSyncConfig(self.handle, config) };
+        if status != OK {
+            return Err(Error::scepter("set real time sync config", status));
+        }
+        Ok(())
+    }
+
+    /// Returns the time synchronization of the device clock. The NTP server is `0.0.0.0` if the device reports no valid address.
+    pub fn get_time_sync(&self) -> Result<TimeSync, Error> {
+        let mut config = sys::ScTimeSyncConfig::default();
+        let status = unsafe { sys::scGetRealTimeSyncConfig(self.handle, &mut config) };
+        if status != OK {
+            return Err(Error::scepter("get real time sync config", status));
+        }
+        Ok(match config.flag {
+            1 => {
+                let ip = config.ip.map(|b| b as std::os::raw::c_char);
+                TimeSync::Ntp {
+                    server: string_from_c_chars(&ip)
+                        .parse()
+                        .unwrap_or(Ipv4Addr::UNSPECIFIED),
+                }
+            }
+            2 => TimeSync::Ptp,
+            _ => TimeSync::Off,
+        })
+    }
+}
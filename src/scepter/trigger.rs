@@ -0,0 +1,149 @@
+//! Work modes of the camera. Besides streaming continuously, a frame can be triggered by software with `trigger_once()` or by a signal on the trigger input, e.g. from an encoder.
+
+use sys::ScStatus_SC_OK as OK;
+use vzense_sys::scepter as sys;
+
+use crate::{Error, frame::FrameSet};
+
+use super::{device::Device, frame};
+
+/// Work modes of the camera.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum WorkMode {
+    /// The camera streams continuously with the frame rate set by `set_frame_rate()`.
+    Active,
+    /// The camera captures a frame for each call of `trigger_once()`. `frame_count` (1 to 10) frames are captured and processed into one frame, more frames improve the quality of the depth frame.
+    SoftwareTrigger { frame_count: u8 },
+    /// The camera captures a frame for each pulse on the trigger input.
+    HardwareTrigger(TriggerSignal),
+}
+
+/// Polarity of the signal on the trigger input.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Polarity {
+    ActiveLow,
+    ActiveHigh,
+}
+
+/// Parameters of the signal on the trigger input in hardware trigger mode.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct TriggerSignal {
+    /// The width of the signal, at least 1.
+    pub width: u16,
+    /// The interval of the signal, at least 34000.
+    pub interval: u16,
+    pub polarity: Polarity,
+}
+
+impl Device {
+    /// Sets the work mode of the camera. The API enables the time filter in `WorkMode::Active` and disables it in the trigger modes. Returns `Error::InvalidParameter` if the settings of the mode are out of range.
+    pub fn set_work_mode(&self, mode: WorkMode) -> Result<(), Error> {
+        match mode {
+            WorkMode::Active => self.set_sys_work_mode(sys::ScWorkMode_SC_ACTIVE_MODE),
+            WorkMode::SoftwareTrigger { frame_count } => {
+                if !(1..=10).contains(&frame_count) {
+                    return Err(Error::InvalidParameter(
+                        "software trigger frame count must be in the range of 1 to 10",
+                    ));
+                }
+                self.set_sys_work_mode(sys::ScWorkMode_SC_SOFTWARE_TRIGGER_MODE)?;
+                let status =
+                    unsafe { sys::scSetSoftwareTriggerParameter(self.handle, frame_count) };
+                if status != OK {
+                    return Err(Error::scepter("set software trigger parameter", status));
+                }
+                Ok(())
+            }
+            WorkMode::HardwareTrigger(signal) => {
+                if signal.width == 0 {
+                    return Err(Error::InvalidParameter(
+                        "trigger signal width must be at least 1",
+                    ));
+                }
+                if signal.interval < 34000 {
+                    return Err(Error::InvalidParameter(
+                        "trigger signal interval must be at least 34000",
+                    ));
+                }
+                self.set_sys_work_mode(sys::ScWorkMode_SC_HARDWARE_TRIGGER_MODE)?;
+                let params = sys::ScInputSignalParamsForHWTrigger {
+                    width: signal.width,
+                    interval: signal.interval,
+                    polarity: match signal.polarity {
+                        Polarity::ActiveLow => 0,
+                        Polarity::ActiveHigh => 1,
+                    },
+                };
+                let status =
+                    unsafe { sys::scSetInputSignalParamsForHWTrigger(self.handle, params) };
+                if status != OK {
+                    return Err(Error::scepter(
+                        "set input signal params for hardware trigger",
+                        status,
+                    ));
+                }
+                Ok(())
+            }
+        }
+    }
+
+    /// Returns the current work mode including its settings.
+    pub fn get_work_mode(&self) -> Result<WorkMode, Error> {
+        let mut mode = sys::ScWorkMode::default();
+        let status = unsafe { sys::scGetWorkMode(self.handle, &mut mode) };
+        if status != OK {
+            return Err(Error::scepter("get work mode", status));
+        }
+        match mode {
+            sys::ScWorkMode_SC_SOFTWARE_TRIGGER_MODE => {
+                let mut frame_count = 0;
+                let status =
+                    unsafe { sys::scGetSoftwareTriggerParameter(self.handle, &mut frame_count) };
+                if status != OK {
+                    return Err(Error::scepter("get software trigger parameter", status));
+                }
+                Ok(WorkMode::SoftwareTrigger { frame_count })
+            }
+            sys::ScWorkMode_SC_HARDWARE_TRIGGER_MODE => {
+                let mut params = sys::ScInputSignalParamsForHWTrigger::default();
+                let status =
+                    unsafe { sys::scGetInputSignalParamsForHWTrigger(self.handle, &mut params) };
+                if status != OK {
+                    return Err(Error::scepter(
+                        "get input signal params for hardware trigger",
+                        status,
+                    ));
+                }
+                Ok(WorkMode::HardwareTrigger(TriggerSignal {
+                    width: params.width,
+                    interval: params.interval,
+                    polarity: if params.polarity == 0 {
+                        Polarity::ActiveLow
+                    } else {
+                        Polarity::ActiveHigh
+                    },
+                }))
+            }
+            _ => Ok(WorkMode::Active),
+        }
+    }
+
+    /// Triggers a single capture in `WorkMode::SoftwareTrigger` and returns the resulting frames. `max_wait_time_ms` is the maximum waiting time for the frames, which includes the capture of all `frame_count` frames.
+    pub fn trigger_once(&mut self, max_wait_time_ms: u16) -> Result<FrameSet, Error> {
+        let status = unsafe { sys::scSoftwareTriggerOnce(self.handle) };
+        if status != OK {
+            return Err(Error::scepter("software trigger once", status));
+        }
+        frame::read_frame_set(self, max_wait_time_ms)
+    }
+
+    // private functions_______________________________________________________
+
+    fn set_sys_work_mode(&self, mode: sys::ScWorkMode) -> Result<(), Error> {
+        let status = unsafe { sys::scSetWorkMode(self.handle, mode) };
+        if status != OK {
+            return Err(Error::scepter("set work mode", status));
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,339 @@
+//! Capturing frames on a background thread and passing them to the application through a bounded channel.
+
+use std::{
+    collections::VecDeque,
+    sync::{
+        Arc, Condvar, Mutex, PoisonError,
+        atomic::{AtomicBool, Ordering},
+    },
+    thread::{self, JoinHandle},
+    time::Duration,
+};
+
+use crate::{Error, camera::Camera, frame::FrameSet};
+
+/// The number of errors in a row (other than timeouts) after which the capture thread ends.
+pub const MAX_CONSECUTIVE_ERRORS: u32 = 10;
+
+/// The pause after an error is this time multiplied by the number of errors in a row.
+const ERROR_BACKOFF: Duration = Duration::from_millis(50);
+
+/// What to do with a new frame set if the channel is full.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum DropPolicy {
+    /// Discard the oldest frame set in the channel to make room, so the application always gets the latest data.
+    DropOldest,
+    /// Discard the new frame set, so the application gets a gapless sequence until the channel is full.
+    DropNewest,
+}
+
+/// A running capture thread, created by `spawn_stream()`. Dropping it stops the thread.
+pub struct FrameStream<D: Camera + ?Sized + 'static> {
+    queue: Arc<Queue>,
+    stop: Arc<AtomicBool>,
+    thread: Option<JoinHandle<Box<D>>>,
+}
+impl<D: Camera + ?Sized + 'static> FrameStream<D> {
+    /// Blocks until the next frame set is available. Returns `None` if the capture thread has ended and all frame sets were received.
+    pub fn recv(&self) -> Option<Result<FrameSet, Error>> {
+        let mut state = self.queue.lock();
+        loop {
+            if let Some(frame_set) = state.frame_sets.pop_front() {
+                return Some(frame_set);
+            }
+            if state.is_closed {
+                return None;
+            }
+            state = self
+                .queue
+                .ready
+                .wait(state)
+                .unwrap_or_else(PoisonError::into_inner);
+        }
+    }
+
+    /// Like `recv()` but waits at most `timeout`. Also returns `None` if no frame set arrived in time.
+    pub fn recv_timeout(&self, timeout: Duration) -> Option<Result<FrameSet, Error>> {
+        let state = self.queue.lock();
+        let (mut state, _) = self
+            .queue
+            .ready
+            .wait_timeout_while(state, timeout, |state| {
+                state.frame_sets.is_empty() && !state.is_closed
+            })
+            .unwrap_or_else(PoisonError::into_inner);
+        state.frame_sets.pop_front()
+    }
+
+    /// Returns the next frame set if one is available without blocking.
+    pub fn try_recv(&self) -> Option<Result<FrameSet, Error>> {
+        self.queue.lock().frame_sets.pop_front()
+    }
+
+    /// `true` if the capture thread has ended, e.g. after the device was disconnected.
+    pub fn is_finished(&self) -> bool {
+        self.queue.lock().is_closed
+    }
+
+    /// The number of frame sets discarded so far because the channel was full.
+    pub fn dropped_count(&self) -> u64 {
+        self.queue.lock().dropped_count
+    }
+
+    /// Stops the capture thread and returns the device. The stream of the device keeps running.
+    pub fn stop(mut self) -> Box<D> {
+        self.stop.store(true, Ordering::Relaxed);
+        let thread = self.thread.take().unwrap();
+        match thread.join() {
+            Ok(device) => device,
+            Err(panic) => std::panic::resume_unwind(panic),
+        }
+    }
+}
+
+impl<D: Camera + ?Sized + 'static> Drop for FrameStream<D> {
+    fn drop(&mut self) {
+        self.stop.store(true, Ordering::Relaxed);
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+    }
+}
+
+/// Moves `device` to a new thread which captures frames continuously and puts the frame sets into a channel holding at most `capacity` frame sets. If the channel is full, `policy` decides which frame set is discarded. `max_wait_time_ms` is passed to `read_next_frame()`; timeouts are skipped, other errors are passed on with an increasing pause after each. The thread ends after the connection to the device was lost or after `MAX_CONSECUTIVE_ERRORS` errors in a row. The error ending the thread is always delivered, even if the channel is full.
+pub fn spawn_stream<D: Camera + ?Sized + 'static>(
+    device: Box<D>,
+    capacity: usize,
+    policy: DropPolicy,
+    max_wait_time_ms: u16,
+) -> FrameStream<D> {
+    let queue = Queue::new(capacity, policy);
+    let stop = Arc::new(AtomicBool::new(false));
+    let thread = spawn_capture(device, &queue, &stop, max_wait_time_ms, false);
+    FrameStream {
+        queue,
+        stop,
+        thread: Some(thread),
+    }
+}
+
+/// Like `spawn_stream()` but returns a `futures::Stream` of frame sets. Dropping the stream stops the capture thread, which then stops the stream of the device (`scStopStream` or `Ps2_StopStream`) and drops the device, without blocking the async runtime.
+#[cfg(feature = "async")]
+pub fn async_stream<D: Camera + ?Sized + 'static>(
+    device: Box<D>,
+    capacity: usize,
+    policy: DropPolicy,
+    max_wait_time_ms: u16,
+) -> AsyncFrameStream<D> {
+    let queue = Queue::new(capacity, policy);
+    let stop = Arc::new(AtomicBool::new(false));
+    spawn_capture(device, &queue, &stop, max_wait_time_ms, true);
+    AsyncFrameStream {
+        queue,
+        stop,
+        device: std::marker::PhantomData,
+    }
+}
+
+/// An asynchronous stream of frame sets captured on a background thread, created by `async_stream()`.
+#[cfg(feature = "async")]
+pub struct AsyncFrameStream<D: Camera + ?Sized + 'static> {
+    queue: Arc<Queue>,
+    stop: Arc<AtomicBool>,
+    device: std::marker::PhantomData<fn() -> Box<D>>,
+}
+#[cfg(feature = "async")]
+impl<D: Camera + ?Sized + 'static> AsyncFrameStream<D> {
+    /// The number of frame sets discarded so far because the channel was full.
+    pub fn dropped_count(&self) -> u64 {
+        self.queue.lock().dropped_count
+    }
+}
+
+#[cfg(feature = "async")]
+impl<D: Camera + ?Sized + 'static> futures::Stream for AsyncFrameStream<D> {
+    type Item = Result<FrameSet, Error>;
+
+    fn poll_next(
+        self: std::pin::Pin<&mut Self>,
+        cx: &mut std::task::Context<'_>,
+    ) -> std::task::Poll<Option<Self::Item>> {
+        let mut state = self.queue.lock();
+        if let Some(frame_set) = state.frame_sets.pop_front() {
+            return std::task::Poll::Ready(Some(frame_set));
+        }
+        if state.is_closed {
+            return std::task::Poll::Ready(None);
+        }
+        state.waker = Some(cx.waker().clone());
+        std::task::Poll::Pending
+    }
+}
+
+#[cfg(feature = "async")]
+impl<D: Camera + ?Sized + 'static> Drop for AsyncFrameStream<D> {
+    fn drop(&mut self) {
+        // the capture thread finishes on its own, joining it could block the runtime for `max_wait_time_ms`
+        self.stop.store(true, Ordering::Relaxed);
+    }
+}
+
+// private functions_______________________________________________________
+
+/// Spawns the thread capturing frames from `device` into `queue` until `stop` is set or the device is disconnected.
+fn spawn_capture<D: Camera + ?Sized + 'static>(
+    mut device: Box<D>,
+    queue: &Arc<Queue>,
+    stop: &Arc<AtomicBool>,
+    max_wait_time_ms: u16,
+    stop_device_stream: bool,
+) -> JoinHandle<Box<D>> {
+    let queue = queue.clone();
+    let stop = stop.clone();
+    thread::spawn(move || {
+        // closes the queue also if the device panics, so receivers do not wait forever
+        let queue = CloseOnDrop(queue);
+        let mut error_count = 0;
+        while !stop.load(Ordering::Relaxed) {
+            match device.read_next_frame(max_wait_time_ms) {
+                Ok(()) => {
+                    error_count = 0;
+                    queue.0.push(device.frame_set());
+                }
+                Err(Error::Timeout) => continue,
+                Err(Error::Disconnected) => {
+                    queue.0.push_terminal(Error::Disconnected);
+                    break;
+                }
+                Err(e) => {
+                    error_count += 1;
+                    if error_count >= MAX_CONSECUTIVE_ERRORS {
+                        queue.0.push_terminal(e);
+                        break;
+                    }
+                    queue.0.push(Err(e));
+                    // a persistent error would otherwise spin the thread and flood the channel
+                    thread::sleep(ERROR_BACKOFF * error_count);
+                }
+            }
+        }
+        drop(queue);
+        if stop_device_stream {
+            let _ = device.stop_stream();
+        }
+        device
+    })
+}
+
+/// Closes the queue when dropped, including the unwinding of a panic in the capture thread.
+struct CloseOnDrop(Arc<Queue>);
+impl Drop for CloseOnDrop {
+    fn drop(&mut self) {
+        self.0.close();
+    }
+}
+
+struct State {
+    frame_sets: VecDeque<Result<FrameSet, Error>>,
+    is_closed: bool,
+    dropped_count: u64,
+    #[cfg(feature = "async")]
+    waker: Option<std::task::Waker>,
+}
+
+/// A bounded queue shared by the capture thread and `FrameStream`.
+struct Queue {
+    state: Mutex<State>,
+    ready: Condvar,
+    capacity: usize,
+    policy: DropPolicy,
+}
+impl Queue {
+    fn new(capacity: usize, policy: DropPolicy) -> Arc<Self> {
+        Arc::new(Queue {
+            state: Mutex::new(State {
+                frame_sets: VecDeque::with_capacity(capacity),
+                is_closed: false,
+                dropped_count: 0,
+                #[cfg(feature = "async")]
+                waker: None,
+            }),
+            ready: Condvar::new(),
+            capacity: capacity.max(1),
+            policy,
+        })
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, State> {
+        self.state.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    fn push(&self, frame_set: Result<FrameSet, Error>) {
+        self.insert(frame_set, false);
+    }
+
+    /// Pushes the error ending the capture thread. It is never discarded: with `DropNewest` it replaces the newest frame set if the channel is full.
+    fn push_terminal(&self, error: Error) {
+        self.insert(Err(error), true);
+    }
+
+    fn insert(&self, frame_set: Result<FrameSet, Error>, is_terminal: bool) {
+        let mut state = self.lock();
+        if state.frame_sets.len() >= self.capacity {
+            state.dropped_count += 1;
+            match self.policy {
+                DropPolicy::DropOldest => {
+                    state.frame_sets.pop_front();
+                }
+                DropPolicy::DropNewest if is_terminal => {
+                    state.frame_sets.pop_back();
+                }
+                DropPolicy::DropNewest => return,
+            }
+        }
+        state.frame_sets.push_back(frame_set);
+        self.ready.notify_one();
+        #[cfg(feature = "async")]
+        if let Some(waker) = state.waker.take() {
+            waker.wake();
+        }
+    }
+
+    fn close(&self) {
+        let mut state = self.lock();
+        state.is_closed = true;
+        self.ready.notify_all();
+        #[cfg(feature = "async")]
+        if let Some(waker) = state.waker.take() {
+            waker.wake();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::camera::mock::MockCamera;
+
+    #[test]
+    fn panic_closes_stream() {
+        let device = MockCamera {
+            panic_on_read: true,
+            ..Default::default()
+        };
+        let stream = spawn_stream(Box::new(device), 4, DropPolicy::DropOldest, 100);
+        assert!(stream.recv().is_none());
+        assert!(stream.is_finished());
+    }
+
+    #[test]
+    fn disconnect_ends_stream() {
+        let device =
+            MockCamera::with_reads([Ok(()), Err(Error::Timeout), Err(Error::Disconnected)]);
+        let stream = spawn_stream(Box::new(device), 4, DropPolicy::DropNewest, 100);
+        assert!(stream.recv().unwrap().is_ok());
+        assert_eq!(stream.recv().unwrap().unwrap_err(), Error::Disconnected);
+        assert!(stream.recv().is_none());
+        assert!(stream.is_finished());
+    }
+}
@@ -0,0 +1,458 @@
+//! A wrapper around a device which reconnects automatically after the connection was lost.
+
+use std::{any::Any, sync::Arc, time::Duration};
+
+use crate::{
+    Alignment, ColorFormat, ColorResolution, DepthMeasuringRange, DeviceInfo, Error,
+    camera::{self, Backend, Camera},
+    cyan, dcam560,
+    filter::DepthFilterConfig,
+    red, scepter, yellow,
+};
+
+/// Connection state of the supervised device.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ConnectionState {
+    /// The device is open and streaming.
+    Connected,
+    /// The connection was lost and the device is being reopened.
+    Reconnecting,
+    /// Reopening the device failed. Another attempt is made with the next `read_next_frame()`.
+    Disconnected,
+}
+
+/// Settings applied through the supervisor, restored after reconnecting.
+#[derive(Default)]
+struct Settings {
+    frame_rate: Option<u8>,
+    color_format: Option<ColorFormat>,
+    alignment: Option<Alignment>,
+    color_resolution: Option<ColorResolution>,
+    depth_range: Option<(u16, u16)>,
+    depth_measuring_range: Option<DepthMeasuringRange>,
+    depth_filters: DepthFilterConfig,
+}
+
+/// Opens the device described by the info within the scan time, replaced by mock devices in tests.
+type Opener = Box<dyn FnMut(&DeviceInfo, Duration, bool) -> Result<Box<dyn Camera>, Error> + Send>;
+
+/**
+Supervises a device identified by its serial number. If `read_next_frame()` reports that the device is in limbo, or it times out `max_timeouts` times in a row, the device is reopened by serial number, the settings applied through the supervisor are restored, and the stream is resumed.
+
+Settings must be applied through the supervisor (not through `device()`) to be restored after reconnecting. State transitions can be observed with `on_state_change()`, settings which could not be restored with `last_restore_errors()`.
+*/
+pub struct Supervisor {
+    info: DeviceInfo,
+    device: Option<Box<dyn Camera>>,
+    // keeps the API initialized while reconnecting, so the device stays known to it
+    sdk: Option<Arc<dyn Any + Send + Sync>>,
+    settings: Settings,
+    restore_errors: Vec<Error>,
+    open: Opener,
+    state: ConnectionState,
+    on_state_change: Option<Box<dyn FnMut(ConnectionState) + Send>>,
+    scan_time: Duration,
+    max_timeouts: u32,
+    timeouts: u32,
+    verbose: bool,
+}
+impl Supervisor {
+    /// Opens the device described by `info` as returned by `camera::list_devices()`. `scan_time` is the maximum time used to search for the device when reconnecting.
+    pub fn new(info: DeviceInfo, scan_time: Duration, verbose: bool) -> Result<Self, Error> {
+        let sdk: Arc<dyn Any + Send + Sync> = match info.backend {
+            Backend::Scepter => scepter::sdk::Sdk::get(verbose)?,
+            Backend::Dcam560 => dcam560::sdk::Sdk::get(verbose)?,
+        };
+        let device = camera::open(&info, verbose)?;
+        let open: Opener = Box::new(|info, scan_time, verbose| {
+            camera::open_by_serial(info.backend, &info.serial, scan_time, verbose)
+        });
+        let mut supervisor = Supervisor::with_device(info, device, open, scan_time, verbose);
+        supervisor.sdk = Some(sdk);
+        Ok(supervisor)
+    }
+
+    /// The info of the supervised device.
+    pub fn info(&self) -> &DeviceInfo {
+        &self.info
+    }
+
+    /// The current connection state.
+    pub fn state(&self) -> ConnectionState {
+        self.state
+    }
+
+    /// The errors of restoring the settings after the last reconnect, empty if all settings were restored.
+    pub fn last_restore_errors(&self) -> &[Error] {
+        &self.restore_errors
+    }
+
+    /// Calls `callback` on every change of the connection state.
+    pub fn on_state_change(&mut self, callback: impl FnMut(ConnectionState) + Send + 'static) {
+        self.on_state_change = Some(Box::new(callback));
+    }
+
+    /// Sets the number of consecutive timeouts after which the connection is considered lost, default is 5.
+    pub fn set_max_timeouts(&mut self, max_timeouts: u32) {
+        self.max_timeouts = max_timeouts;
+    }
+
+    /// The supervised device to get frames from, `None` while disconnected.
+    pub fn device(&mut self) -> Option<&mut dyn Camera> {
+        match &mut self.device {
+            Some(device) => Some(device.as_mut()),
+            None => None,
+        }
+    }
+
+    /// Captures the next image frame, see `Camera::read_next_frame()`. If the connection is lost, reconnecting is tried before returning the error. While disconnected, every call tries to reconnect first.
+    pub fn read_next_frame(&mut self, max_wait_time_ms: u16) -> Result<(), Error> {
+        if self.device.is_none() {
+            self.reconnect()?;
+        }
+        let Some(device) = &mut self.device else {
+            return Err(Error::Disconnected);
+        };
+        match device.read_next_frame(max_wait_time_ms) {
+            Ok(()) => {
+                self.timeouts = 0;
+                Ok(())
+            }
+            Err(Error::Timeout) => {
+                self.timeouts += 1;
+                if self.timeouts >= self.max_timeouts {
+                    // a failed reconnect is reported by the state, the timeout is returned either way
+                    let _ = self.reconnect();
+                }
+                Err(Error::Timeout)
+            }
+            Err(Error::Disconnected) => {
+                let _ = self.reconnect();
+                Err(Error::Disconnected)
+            }
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Set the ToF frame rate, restored after reconnecting.
+    pub fn set_frame_rate(&mut self, rate: u8) -> Result<(), Error> {
+        if let Some(device) = &self.device {
+            device.set_frame_rate(rate)?;
+        }
+        self.settings.frame_rate = Some(rate);
+        Ok(())
+    }
+
+    /// Set the color frame format to either RGB or BGR, restored after reconnecting.
+    pub fn set_color_format(&mut self, format: ColorFormat) {
+        self.settings.color_format = Some(format);
+        if let Some(device) = &self.device {
+            device.set_color_format(format);
+        }
+    }
+
+    /// Enable or disable the mapping of the color image to depth camera space, restored after reconnecting.
+    pub fn map_color_to_depth(&mut self, is_enabled: bool) {
+        let alignment = if is_enabled {
+            Alignment::ColorToDepth
+        } else {
+            Alignment::None
+        };
+        if let Err(e) = self.set_alignment(alignment) {
+            println!("{}", red!("{}", e));
+        }
+    }
+
+    /// Sets how depth and color frames are aligned, restored after reconnecting.
+    pub fn set_alignment(&mut self, alignment: Alignment) -> Result<(), Error> {
+        if let Some(device) = &mut self.device {
+            device.set_alignment(alignment)?;
+        }
+        self.settings.alignment = Some(alignment);
+        Ok(())
+    }
+
+    /// Sets the resolution of the color frame, restored after reconnecting.
+    pub fn set_color_resolution(&mut self, resolution: ColorResolution) -> Result<(), Error> {
+        if let Some(device) = &mut self.device {
+            device.set_color_resolution(resolution)?;
+        }
+        self.settings.color_resolution = Some(resolution);
+        Ok(())
+    }
+
+    /// Choosing the min/max depth in mm for the color mapping of the depth output, restored after reconnecting.
+    pub fn set_depth_range(&mut self, min_depth_mm: u16, max_depth_mm: u16) {
+        self.settings.depth_range = Some((min_depth_mm, max_depth_mm));
+        if let Some(device) = &mut self.device {
+            device.set_depth_range(min_depth_mm, max_depth_mm);
+        }
+    }
+
+    /// Sets the depth measuring range of a DCAM560, restored after reconnecting. Returns `Error::Unsupported` for Scepter devices.
+    pub fn set_depth_measuring_range(
+        &mut self,
+        depth_range: DepthMeasuringRange,
+    ) -> Result<(), Error> {
+        if let Some(device) = &self.device {
+            device.set_depth_measuring_range(depth_range)?;
+        }
+        self.settings.depth_measuring_range = Some(depth_range);
+        Ok(())
+    }
+
+    /// Applies all settings of the depth filter configuration which are not `None`, restored after reconnecting.
+    pub fn set_depth_filters(&mut self, config: &DepthFilterConfig) -> Result<(), Error> {
+        if let Some(device) = &self.device {
+            device.set_depth_filters(config)?;
+        }
+        let filters = &mut self.settings.depth_filters;
+        *filters = DepthFilterConfig {
+            time_filter: config.time_filter.or(filters.time_filter),
+            time_filter_threshold: config
+                .time_filter_threshold
+                .or(filters.time_filter_threshold),
+            confidence_filter: config.confidence_filter.or(filters.confidence_filter),
+            confidence_threshold: config.confidence_threshold.or(filters.confidence_threshold),
+            flying_pixel_filter: config.flying_pixel_filter.or(filters.flying_pixel_filter),
+            flying_pixel_threshold: config
+                .flying_pixel_threshold
+                .or(filters.flying_pixel_threshold),
+            spatial_filter: config.spatial_filter.or(filters.spatial_filter),
+            fill_hole_filter: config.fill_hole_filter.or(filters.fill_hole_filter),
+        };
+        Ok(())
+    }
+
+    /// Stops the stream and closes the device. The API is shut down when no other device of the same API is alive.
+    pub fn shut_down(&mut self, verbose: bool) {
+        if let Some(mut device) = self.device.take() {
+            device.shut_down(verbose);
+        }
+        self.sdk = None;
+    }
+
+    // private functions_______________________________________________________
+
+    fn with_device(
+        info: DeviceInfo,
+        device: Box<dyn Camera>,
+        open: Opener,
+        scan_time: Duration,
+        verbose: bool,
+    ) -> Self {
+        Supervisor {
+            info,
+            device: Some(device),
+            sdk: None,
+            settings: Settings::default(),
+            restore_errors: Vec::new(),
+            open,
+            state: ConnectionState::Connected,
+            on_state_change: None,
+            scan_time,
+            max_timeouts: 5,
+            timeouts: 0,
+            verbose,
+        }
+    }
+
+    fn set_state(&mut self, state: ConnectionState) {
+        if self.state != state {
+            self.state = state;
+            if let Some(callback) = &mut self.on_state_change {
+                callback(state);
+            }
+        }
+    }
+
+    /// Closes the lost device, searches for the device with the same serial number, opens it, and restores the settings.
+    fn reconnect(&mut self) -> Result<(), Error> {
+        self.set_state(ConnectionState::Reconnecting);
+        self.timeouts = 0;
+        if let Some(mut device) = self.device.take() {
+            device.shut_down(false);
+        }
+        if self.verbose {
+            println!(
+                "{}",
+                yellow!(
+                    "reconnecting device with serial number {}",
+                    self.info.serial
+                )
+            );
+        }
+        match (self.open)(&self.info, self.scan_time, self.verbose) {
+            Ok(device) => {
+                self.device = Some(device);
+                self.restore_errors = self.restore_settings();
+                if self.verbose {
+                    for e in &self.restore_errors {
+                        println!("{}", red!("restoring settings failed: {}", e));
+                    }
+                    println!("{}", cyan!("device reconnected"));
+                }
+                self.set_state(ConnectionState::Connected);
+                Ok(())
+            }
+            Err(e) => {
+                self.set_state(ConnectionState::Disconnected);
+                Err(e)
+            }
+        }
+    }
+
+    /// Applies all stored settings to the reopened device. A failing setting does not keep the others from being restored, all errors are returned.
+    fn restore_settings(&mut self) -> Vec<Error> {
+        let mut errors = Vec::new();
+        let Some(device) = &mut self.device else {
+            return errors;
+        };
+        let settings = &self.settings;
+        if let Some(format) = settings.color_format {
+            device.set_color_format(format);
+        }
+        if let Some(alignment) = settings.alignment
+            && let Err(e) = device.set_alignment(alignment)
+        {
+            errors.push(e);
+        }
+        if let Some(resolution) = settings.color_resolution
+            && let Err(e) = device.set_color_resolution(resolution)
+        {
+            errors.push(e);
+        }
+        if let Some((min_depth_mm, max_depth_mm)) = settings.depth_range {
+            device.set_depth_range(min_depth_mm, max_depth_mm);
+        }
+        if let Some(depth_range) = settings.depth_measuring_range
+            && let Err(e) = device.set_depth_measuring_range(depth_range)
+        {
+            errors.push(e);
+        }
+        if let Some(rate) = settings.frame_rate
+            && let Err(e) = device.set_frame_rate(rate)
+        {
+            errors.push(e);
+        }
+        if settings.depth_filters != DepthFilterConfig::default()
+            && let Err(e) = device.set_depth_filters(&settings.depth_filters)
+        {
+            errors.push(e);
+        }
+        errors
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        collections::VecDeque,
+        sync::{Arc, Mutex},
+    };
+
+    use super::*;
+    use crate::{ConnectStatus, camera::mock::MockCamera};
+
+    /// A supervisor of `device` which gets the results of `reopened` when reconnecting, and the recorded state changes.
+    fn supervise(
+        device: MockCamera,
+        reopened: impl IntoIterator<Item = Result<MockCamera, Error>>,
+    ) -> (Supervisor, Arc<Mutex<Vec<ConnectionState>>>) {
+        let info = DeviceInfo {
+            backend: Backend::Scepter,
+            model: "mock".to_string(),
+            serial: "0".to_string(),
+            ip: String::new(),
+            status: ConnectStatus::Opened,
+        };
+        let mut reopened: VecDeque<_> = reopened.into_iter().collect();
+        let open: Opener = Box::new(move |_, _, _| match reopened.pop_front() {
+            Some(device) => Ok(Box::new(device?) as Box<dyn Camera>),
+            None => Err(Error::NoDeviceFound),
+        });
+        let mut supervisor =
+            Supervisor::with_device(info, Box::new(device), open, Duration::ZERO, false);
+        let states = Arc::new(Mutex::new(Vec::new()));
+        let recorded = states.clone();
+        supervisor.on_state_change(move |state| recorded.lock().unwrap().push(state));
+        (supervisor, states)
+    }
+
+    #[test]
+    fn reconnect_after_max_timeouts() {
+        let device = MockCamera::with_reads(std::iter::repeat_n(Err(Error::Timeout), 3));
+        let (mut supervisor, states) = supervise(device, [Ok(MockCamera::default())]);
+        supervisor.set_max_timeouts(3);
+        for _ in 0..2 {
+            assert_eq!(supervisor.read_next_frame(0), Err(Error::Timeout));
+        }
+        assert!(states.lock().unwrap().is_empty());
+        assert_eq!(supervisor.read_next_frame(0), Err(Error::Timeout));
+        assert_eq!(
+            *states.lock().unwrap(),
+            [ConnectionState::Reconnecting, ConnectionState::Connected]
+        );
+        assert_eq!(supervisor.read_next_frame(0), Ok(()));
+    }
+
+    #[test]
+    fn reconnect_after_failed_attempt() {
+        let device = MockCamera::with_reads([Err(Error::Disconnected)]);
+        let (mut supervisor, states) = supervise(
+            device,
+            [Err(Error::NoDeviceFound), Ok(MockCamera::default())],
+        );
+        assert_eq!(supervisor.read_next_frame(0), Err(Error::Disconnected));
+        assert_eq!(supervisor.state(), ConnectionState::Disconnected);
+        assert!(supervisor.device().is_none());
+        assert_eq!(supervisor.read_next_frame(0), Ok(()));
+        assert_eq!(
+            *states.lock().unwrap(),
+            [
+                ConnectionState::Reconnecting,
+                ConnectionState::Disconnected,
+                ConnectionState::Reconnecting,
+                ConnectionState::Connected,
+            ]
+        );
+    }
+
+    #[test]
+    fn restore_settings() {
+        let device = MockCamera::with_reads([Err(Error::Disconnected)]);
+        let (mut supervisor, _) = supervise(device, [Ok(MockCamera::default())]);
+        assert_eq!(supervisor.set_frame_rate(20), Ok(()));
+        assert!(supervisor.set_frame_rate(60).is_err());
+        assert_eq!(supervisor.set_alignment(Alignment::DepthToColor), Ok(()));
+        assert!(
+            supervisor
+                .set_depth_measuring_range(DepthMeasuringRange::Near)
+                .is_err()
+        );
+        assert_eq!(supervisor.read_next_frame(0), Err(Error::Disconnected));
+        assert_eq!(supervisor.state(), ConnectionState::Connected);
+        // rejected settings are not restored and cause no errors
+        assert!(supervisor.last_restore_errors().is_empty());
+        let device = supervisor.device().unwrap();
+        assert_eq!(device.get_frame_rate(), Ok(20));
+        assert_eq!(device.get_alignment(), Alignment::DepthToColor);
+    }
+
+    #[test]
+    fn keep_restore_errors() {
+        let device = MockCamera::with_reads([Err(Error::Disconnected)]);
+        let reopened = MockCamera {
+            max_frame_rate: 10,
+            ..Default::default()
+        };
+        let (mut supervisor, _) = supervise(device, [Ok(reopened)]);
+        assert_eq!(supervisor.set_frame_rate(20), Ok(()));
+        assert_eq!(supervisor.read_next_frame(0), Err(Error::Disconnected));
+        assert_eq!(supervisor.state(), ConnectionState::Connected);
+        assert!(matches!(
+            supervisor.last_restore_errors(),
+            [Error::InvalidParameter(_)]
+        ));
+    }
+}
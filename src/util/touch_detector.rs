@@ -38,7 +38,7 @@ pub struct TouchDetector {
 }
 impl TouchDetector {
     /// Creates a new instance with the specified parameters. All length parameters are in mm.
-    pub fn new<Device: Data>(
+    pub fn new<Device: Data + ?Sized>(
         device: &Device,
         min_touch: f32,
         max_touch: f32,
@@ -65,7 +65,7 @@ impl TouchDetector {
     /// Processes a depth frame resulting in a `touch_signal` (255 for "touch", 0 otherwise) and a `distance` from the initially measured depth in mm.
     ///
     /// **Note**: This function does nothing if the current frame in device is not a depth frame. Call `get_depth_mm_u16_frame()` or `get_depth_scaled_u8_frame()` before calling `process()`.
-    pub fn process<Device: Data>(
+    pub fn process<Device: Data + ?Sized>(
         &mut self,
         device: &Device,
         touch_signal: &mut [u8],